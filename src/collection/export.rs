@@ -0,0 +1,55 @@
+use std::io::{self, Read, Write};
+
+use Val;
+use collection::Collection;
+use meta::Meta;
+
+// Identifies a stream written by `export`, followed by the format version
+const MAGIC: &[u8; 4] = b"coll";
+const VERSION: u8 = 1;
+
+impl<T, M> Collection<T, M>
+    where T: Val,
+          M: Meta<T>
+{
+    /// Writes all the elements of the collection to `w` in order, after a
+    /// small header, encoding each of them with `encode`.
+    ///
+    /// The shape of the tree only depends on the elements, so it does not
+    /// need to be written.
+    pub fn export<W, F>(&self, w: &mut W, mut encode: F) -> io::Result<()>
+        where W: Write,
+              F: FnMut(&T, &mut W) -> io::Result<()>
+    {
+        w.write_all(MAGIC)?;
+        w.write_all(&[VERSION])?;
+        w.write_all(&(self.iter().count() as u64).to_le_bytes())?;
+        for t in self.iter() {
+            encode(t, w)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a collection written by `export` from `r`, decoding each
+    /// element with `decode`, and rebuilds its tree.
+    pub fn import<R, F>(r: &mut R, mut decode: F) -> io::Result<Self>
+        where R: Read,
+              F: FnMut(&mut R) -> io::Result<T>
+    {
+        let mut header = [0; 5];
+        r.read_exact(&mut header)?;
+        if &header[..4] != MAGIC || header[4] != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      "not an exported collection"));
+        }
+        let mut count = [0; 8];
+        r.read_exact(&mut count)?;
+        let count = u64::from_le_bytes(count);
+
+        let mut elements = vec![];
+        for _ in 0..count {
+            elements.push(decode(r)?);
+        }
+        Ok(Self::from_sorted(elements))
+    }
+}
@@ -1,15 +1,35 @@
 use Val;
 
 use std::fmt;
-use std::ops::{Deref, DerefMut};
+use std::mem;
+use std::collections::HashMap;
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut, Range};
 
+#[cfg(feature = "visualize")]
 use html::{Html, CSS};
-use meta::{Meta, SubMeta, Select};
+use meta::{Meta, SubMeta, Select, Selection};
+use meta::cardinality::Cardinality;
+use meta::distinct::Distinct;
+use meta::top_k::{TopK, Capacity};
 use stash::{Location, Stash};
-use tree::node::Node;
+use tree::node::{Node, Child};
 use tree::branch::Branch;
 use tree::level::{Beginning, End, Relative};
 
+mod export;
+#[cfg(feature = "serde")]
+mod serialize;
+
+#[cfg(feature = "serde")]
+pub use self::serialize::deserialize_sorted;
+mod stats;
+mod validate;
+
+pub use self::stats::{Stats, MemoryUsage};
+pub use self::validate::Violation;
+
 
 /// A collection of elements of type T,
 /// with metadata of type M.
@@ -27,6 +47,8 @@ pub struct Collection<T, M>
     /// The branching factor, currently hard-coded to 2, which means on average
     /// every fourth element will have weight > 0.
     pub divisor: usize,
+    // Earlier versions of this collection, by name
+    snapshots: HashMap<String, Collection<T, M>>,
 }
 
 /// A view into a Collection, being able to act as a &mut T wrapper.
@@ -100,6 +122,7 @@ impl<T, M> Collection<T, M>
             root: root,
             stash: stash,
             divisor: 2,
+            snapshots: HashMap::new(),
         }
     }
 
@@ -109,10 +132,82 @@ impl<T, M> Collection<T, M>
             root: root,
             stash: stash,
             divisor: 2,
+            snapshots: HashMap::new(),
+        }
+    }
+
+    /// Returns `true` if the Collection contains no elements.
+    ///
+    /// Only looks at the root node, and needs no particular metadata.
+    pub fn is_empty(&self) -> bool {
+        self.stash.get(self.root).children.is_empty()
+    }
+
+    /// Removes all elements from the Collection, dropping its nodes.
+    ///
+    /// Snapshots, and nodes shared with clones, are kept.
+    pub fn clear(&mut self) {
+        self.adopt(Collection::new());
+    }
+
+    /// Builds a Collection bottom-up from elements that are already in
+    /// order, without searching the tree for each element.
+    ///
+    /// The tree has the same shape as one built by inserting the
+    /// elements one at a time.
+    pub fn from_sorted<I>(iter: I) -> Self
+        where I: IntoIterator<Item = T>
+    {
+        let divisor = 2;
+        let mut stash = Stash::new();
+
+        // The children of the level being built, along with the weight
+        // of the last element below them.
+        let mut level: Vec<(Child<T, M>, usize)> = iter.into_iter()
+            .map(|t| {
+                let weight = t.weight() / divisor;
+                (Child::new_leaf(t), weight)
+            })
+            .collect();
+
+        if level.is_empty() {
+            return Self::new();
+        }
+
+        let mut depth = 0;
+        loop {
+            let len = level.len();
+            let mut above = vec![];
+            let mut node = Node::new();
+
+            for (i, (child, weight)) in level.into_iter().enumerate() {
+                node.children.push_back(child);
+                // The last element of the collection never splits
+                if i + 1 == len || weight > depth {
+                    let meta = node.meta().expect("non-empty").into_owned();
+                    let full = mem::replace(&mut node, Node::new());
+                    let location = stash.put(full);
+                    above.push((Child::new_node(location, meta), weight));
+                }
+            }
+
+            if above.len() == 1 {
+                if let Some((Child::Node { location, .. }, _)) = above.pop() {
+                    return Collection {
+                        root: location,
+                        stash: stash,
+                        divisor: divisor,
+                        snapshots: HashMap::new(),
+                    };
+                }
+            }
+            level = above;
+            depth += 1;
         }
     }
 
     /// Produces a html representation of this Collection. For debug use only.
+    #[cfg(feature = "visualize")]
     pub fn _html(&self) -> String
         where T: fmt::Debug
     {
@@ -121,6 +216,73 @@ impl<T, M> Collection<T, M>
                 self.root._html(self.stash.top()))
     }
 
+    /// Produces a standalone html page showing the tree of this
+    /// Collection, followed by the layout of the nodes in the stash. For
+    /// debug use only.
+    #[cfg(feature = "visualize")]
+    pub fn to_html(&self) -> String
+        where T: fmt::Debug
+    {
+        format!("<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<style>{}</style>
+</head>
+<body>
+{}
+<div class=\"stash\">{}</div>
+</body>
+</html>
+",
+                CSS,
+                self.root._html(self.stash.top()),
+                self.stash._html(self.root.depth))
+    }
+
+    /// Produces an indented text representation of the tree, with the
+    /// `S` metadata of every node and the weight of every leaf. For debug
+    /// use only.
+    pub fn debug_tree_using<S>(&self) -> String
+        where T: fmt::Debug,
+              S: Clone + fmt::Debug,
+              M: SubMeta<S>
+    {
+        let mut s = String::new();
+        self.debug_node::<S>(self.root, 0, &mut s);
+        s
+    }
+
+    // Writes the children of the node at `location` to `s`, indented
+    // `indent` levels.
+    fn debug_node<S>(&self,
+                     location: Location<T, M>,
+                     indent: usize,
+                     s: &mut String)
+        where T: fmt::Debug,
+              S: Clone + fmt::Debug,
+              M: SubMeta<S>
+    {
+        let node = self.stash.get(location);
+        for child in &node.children {
+            for _ in 0..indent {
+                s.push_str("  ");
+            }
+            match *child {
+                Child::Node { location: child_location, ref meta } => {
+                    let child_location =
+                        child_location.relative(location.depth);
+                    let m: Cow<S> = meta.submeta();
+                    s.push_str(&format!("{:?} {:?}\n", child_location, m));
+                    self.debug_node::<S>(child_location, indent + 1, s);
+                }
+                Child::Leaf(ref t) => {
+                    s.push_str(&format!("{:?} weight {}\n", t, t.weight()));
+                }
+            }
+        }
+    }
+
     /// Clones the collection, mutating self
     pub fn clone_mut(&mut self) -> Self {
         let new_stash = self.stash.clone_mut(&mut self.root);
@@ -128,9 +290,113 @@ impl<T, M> Collection<T, M>
             stash: new_stash,
             root: self.root,
             divisor: self.divisor,
+            snapshots: HashMap::new(),
         }
     }
 
+    // Clones the collection without mutating self, copying the nodes
+    // changed since the last `clone_mut`, and sharing the others
+    pub(crate) fn clone_ref(&self) -> Self {
+        Collection {
+            stash: self.stash.clone_ref(),
+            root: self.root,
+            divisor: self.divisor,
+            snapshots: HashMap::new(),
+        }
+    }
+
+    /// Records the current version of the collection under `name`,
+    /// replacing any earlier snapshot with that name.
+    ///
+    /// The snapshot shares its tree with the collection, nothing is copied.
+    pub fn snapshot(&mut self, name: &str) {
+        let snapshot = self.clone_mut();
+        self.snapshots.insert(name.to_owned(), snapshot);
+    }
+
+    /// Makes the version recorded under `name` the current one, keeping
+    /// the snapshot for later use.
+    ///
+    /// Returns `false` if there is no snapshot with that name.
+    pub fn restore_snapshot(&mut self, name: &str) -> bool {
+        let restored = match self.snapshots.get_mut(name) {
+            Some(snapshot) => snapshot.clone_mut(),
+            None => return false,
+        };
+        self.adopt(restored);
+        true
+    }
+
+    // Replaces the tree of the collection with the one of `other`,
+    // keeping the snapshots
+    pub(crate) fn adopt(&mut self, other: Self) {
+        self.root = other.root;
+        self.stash = other.stash;
+    }
+
+    /// Forgets the snapshot recorded under `name`, returning `false` if
+    /// there was none
+    pub fn remove_snapshot(&mut self, name: &str) -> bool {
+        self.snapshots.remove(name).is_some()
+    }
+
+    /// Applies `f` to a copy of the collection, adopting the changes if
+    /// it returns `Ok`, and discarding them if it returns `Err`.
+    ///
+    /// The collection is never left with only some of the changes made
+    /// in `f`, even if `f` panics. The snapshots can be used in `f`, and
+    /// the ones taken or removed there are kept or discarded along with
+    /// the other changes.
+    pub fn transaction<F, R, E>(&mut self, f: F) -> Result<R, E>
+        where F: FnOnce(&mut Self) -> Result<R, E>
+    {
+        let mut scratch = self.clone_mut();
+        // The originals stay in place, in case `f` fails or panics
+        scratch.snapshots = self.snapshots
+            .iter_mut()
+            .map(|(name, snapshot)| (name.clone(), snapshot.clone_mut()))
+            .collect();
+        let result = f(&mut scratch);
+        if result.is_ok() {
+            self.snapshots = mem::take(&mut scratch.snapshots);
+            self.adopt(scratch);
+        }
+        result
+    }
+
+    /// Rewrites the nodes reachable from the root into a fresh stash,
+    /// dropping all the others, and returns the number of nodes dropped.
+    ///
+    /// The collection shares no nodes with its clones afterwards.
+    pub fn compact(&mut self) -> usize {
+        let before = self.stash.len();
+        let mut stash = Stash::new();
+        self.root = compact_node(&self.stash, self.root, &mut stash);
+        self.stash = stash;
+        before - self.stash.len()
+    }
+
+    /// Copies the collection into `target`, putting the nodes reachable
+    /// from its root into the stash of `target`, next to the nodes it
+    /// already holds.
+    ///
+    /// `target` shares no nodes with `self` afterwards, and keeps its own
+    /// snapshots.
+    pub fn clone_into(&self, target: &mut Self) {
+        target.root = compact_node(&self.stash, self.root, &mut target.stash);
+        target.divisor = self.divisor;
+    }
+
+    /// Rebuilds the collection with the metadata `N`, for example to move
+    /// from `CheckSum` to a `CheckSumWith` using another hasher.
+    ///
+    /// Elements keep their order, and the tree keeps its shape.
+    pub fn rehash<N>(&self) -> Collection<T, N>
+        where N: Meta<T>
+    {
+        Collection::from_sorted(self.iter().cloned())
+    }
+
     /// Returns a new, cloned collection that is the result of a union operation
     /// given two Meta implementations `F` and `E`
     ///
@@ -249,6 +515,305 @@ impl<T, M> Collection<T, M>
                     root: branch.root(),
                     stash: stash,
                     divisor: self.divisor,
+                    snapshots: HashMap::new(),
+                }
+            }
+        }
+    }
+
+    /// Returns a new Collection that is the union of all of `sets`, given
+    /// a Meta implementation `F`, used to order the elements.
+    ///
+    /// The stashes are merged once, and the union is constructed in a
+    /// single k-way merge over branches into all the collections.
+    ///
+    /// When more than one collection contains an element, it is picked
+    /// from the one latest in `sets`.
+    pub fn union_many_using<F>(sets: &mut [Self]) -> Self
+        where F: Meta<T> + Select<T> + PartialEq + Ord,
+              M: SubMeta<F>
+    {
+        let (first, rest) = match sets.split_first_mut() {
+            Some(split) => split,
+            None => return Self::new(),
+        };
+        let divisor = first.divisor;
+
+        let mut stash = first.stash.clone_mut(&mut first.root);
+        let mut roots = vec![first.root];
+        for set in rest.iter_mut() {
+            stash = first.stash
+                .merge(&mut first.root, &mut set.root, &mut set.stash);
+            roots.push(set.root);
+        }
+
+        let mut branches: Vec<Option<Branch<_, _, Beginning>>> = roots.iter()
+            .map(|root| Some(Branch::first(*root, &stash)))
+            .collect();
+        // Branch into union, being constructed as we go
+        let mut into: Option<Branch<_, _, End>> = None;
+
+        loop {
+            let keys: Vec<Option<F>> = branches.iter()
+                .map(|b| {
+                    b.as_ref()
+                        .and_then(|b| b.leaf(&stash))
+                        .map(|t| F::from_t(t))
+                })
+                .collect();
+
+            let min = match keys.iter().filter_map(|k| k.as_ref()).min() {
+                Some(min) => min.clone(),
+                None => break,
+            };
+            let next = keys.iter()
+                .filter_map(|k| k.as_ref())
+                .filter(|k| **k != min)
+                .min()
+                .cloned();
+            let holding: Vec<usize> = (0..keys.len())
+                .filter(|i| keys[*i].as_ref() == Some(&min))
+                .collect();
+
+            let (&last, others) = holding.split_last().expect("min exists");
+
+            if others.is_empty() {
+                let mut branch = branches[last].take().expect("is some");
+                match next {
+                    Some(mut key) => {
+                        // take everything up until the next smallest key
+                        branch.find_full(&mut key, &stash);
+                        let left = branch.left(&mut stash);
+                        append_left(&mut into, left, divisor, &mut stash);
+                        branches[last] = Some(branch.right(&mut stash));
+                    }
+                    None => {
+                        // only one collection left, concat it in full
+                        append_rest(&mut into, &branch, divisor, &mut stash);
+                        break;
+                    }
+                }
+            } else {
+                // skip over the equal element in all but the last
+                for i in others {
+                    let mut branch = branches[*i].take().expect("is some");
+                    if branch.step(&stash).is_some() {
+                        branches[*i] = Some(branch.right(&mut stash));
+                    }
+                }
+                let branch = branches[last].take().expect("is some");
+                let mut stepped = branch.clone();
+                if stepped.step(&stash).is_some() {
+                    let left = stepped.left(&mut stash);
+                    append_left(&mut into, left, divisor, &mut stash);
+                    branches[last] = Some(stepped.right(&mut stash));
+                } else {
+                    append_rest(&mut into, &branch, divisor, &mut stash);
+                }
+            }
+        }
+
+        match into {
+            None => Self::new(),
+            Some(branch) => {
+                Collection {
+                    root: branch.root(),
+                    stash: stash,
+                    divisor: divisor,
+                    snapshots: HashMap::new(),
+                }
+            }
+        }
+    }
+
+    /// Returns a new Collection with the elements of `self` that are
+    /// also in `b`, given the same `F` and `E` as `union_using`.
+    ///
+    /// Elements are picked from `self`.
+    pub fn intersection_using<F, E>(&mut self, b: &mut Self) -> Self
+        where F: Meta<T> + Select<T> + PartialEq + Ord,
+              E: Meta<T> + PartialEq,
+              M: SubMeta<F> + SubMeta<E>
+    {
+        self.filter_using::<F, E>(b, true)
+    }
+
+    /// Returns a new Collection with the elements of `self` that are
+    /// not in `b`, given the same `F` and `E` as `union_using`.
+    pub fn difference_using<F, E>(&mut self, b: &mut Self) -> Self
+        where F: Meta<T> + Select<T> + PartialEq + Ord,
+              E: Meta<T> + PartialEq,
+              M: SubMeta<F> + SubMeta<E>
+    {
+        self.filter_using::<F, E>(b, false)
+    }
+
+    // Walks `self` and `b` in order, keeping the elements of `self` that
+    // are in `b` if `common`, or the ones that are not otherwise.
+    fn filter_using<F, E>(&mut self, b: &mut Self, common: bool) -> Self
+        where F: Meta<T> + Select<T> + PartialEq + Ord,
+              E: Meta<T> + PartialEq,
+              M: SubMeta<F> + SubMeta<E>
+    {
+        let a = self.clone_mut();
+        let divisor = self.divisor;
+
+        let mut stash =
+            self.stash.merge(&mut self.root, &mut b.root, &mut b.stash);
+
+        let mut branch_a: Branch<_, _, Beginning> = Branch::first(a.root,
+                                                                  &stash);
+        let mut branch_b: Branch<_, _, Beginning> = Branch::first(b.root,
+                                                                  &stash);
+        // Branch into the result, being constructed as we go
+        let mut into: Option<Branch<_, _, End>> = None;
+
+        loop {
+            let keys = (branch_a.leaf(&stash).map(|t| F::from_t(t)),
+                        branch_b.leaf(&stash).map(|t| F::from_t(t)));
+            match keys {
+                (Some(a), Some(b)) => {
+                    if a == b {
+                        branch_a.skip_equal::<E>(&mut branch_b, &stash);
+                        // equal by `F`, but not by `E`
+                        if branch_a.leaf(&stash).map(|t| F::from_t(t)) ==
+                           Some(a) {
+                            branch_a.step(&stash);
+                            branch_b.step(&stash);
+                        }
+                        // the common elements are now left of branch_a
+                        let left = branch_a.left(&mut stash);
+                        if common {
+                            append_left(&mut into, left, divisor, &mut stash);
+                        }
+                        branch_a = branch_a.right(&mut stash);
+                        branch_b = branch_b.right(&mut stash);
+                    } else if a < b {
+                        let mut b = b;
+                        branch_a.find_full(&mut b, &stash);
+                        let left = branch_a.left(&mut stash);
+                        if !common {
+                            append_left(&mut into, left, divisor, &mut stash);
+                        }
+                        branch_a = branch_a.right(&mut stash);
+                    } else {
+                        let mut a = a;
+                        branch_b.find_full(&mut a, &stash);
+                        branch_b = branch_b.right(&mut stash);
+                    }
+                }
+                (Some(_), None) => {
+                    if !common {
+                        append_rest(&mut into, &branch_a, divisor, &mut stash);
+                    }
+                    break;
+                }
+                (None, _) => break,
+            }
+        }
+
+        match into {
+            None => Self::new(),
+            Some(branch) => {
+                Collection {
+                    root: branch.root(),
+                    stash: stash,
+                    divisor: divisor,
+                    snapshots: HashMap::new(),
+                }
+            }
+        }
+    }
+
+    /// Counts the elements that go before the position selected by
+    /// `search`, by adding up the cardinalities of the subtrees passed
+    /// on the way down the tree.
+    pub fn count_before<S>(&self, mut search: S) -> usize
+        where S: Meta<T> + Select<T>,
+              M: SubMeta<S> + SubMeta<Cardinality<usize>>
+    {
+        let mut count = 0;
+        let mut location = self.root;
+
+        'descend: loop {
+            let node = self.stash.get(location);
+            for child in &node.children {
+                match *child {
+                    Child::Node { location: child_location, ref meta } => {
+                        match search.select(meta.submeta()) {
+                            Selection::Miss => {
+                                let c: Cow<Cardinality<usize>> =
+                                    meta.submeta();
+                                count += *c.inner();
+                            }
+                            Selection::Hit | Selection::Between => {
+                                location =
+                                    child_location.relative(location.depth);
+                                continue 'descend;
+                            }
+                        }
+                    }
+                    Child::Leaf(ref t) => {
+                        match search.select(Cow::Owned(S::from_t(t))) {
+                            Selection::Miss => count += 1,
+                            _ => return count,
+                        }
+                    }
+                }
+            }
+            return count;
+        }
+    }
+
+    /// Returns the metadata `S` of the elements in the index `range`,
+    /// merged in order, or `None` if the range is empty.
+    ///
+    /// Subtrees entirely within the range contribute their metadata
+    /// without being descended into.
+    pub fn query_range<S>(&self, range: Range<usize>) -> Option<S>
+        where S: Meta<T>,
+              M: SubMeta<S> + SubMeta<Cardinality<usize>>
+    {
+        let mut acc = None;
+        self.query_node(self.root, 0, &range, &mut acc);
+        acc
+    }
+
+    // Merges the metadata of the elements of the node at `location`,
+    // whose first element is at index `start`, that are in `range`.
+    fn query_node<S>(&self,
+                     location: Location<T, M>,
+                     mut start: usize,
+                     range: &Range<usize>,
+                     acc: &mut Option<S>)
+        where S: Meta<T>,
+              M: SubMeta<S> + SubMeta<Cardinality<usize>>
+    {
+        let node = self.stash.get(location);
+        for child in &node.children {
+            if start >= range.end {
+                return;
+            }
+            match *child {
+                Child::Node { location: child_location, ref meta } => {
+                    let c: Cow<Cardinality<usize>> = meta.submeta();
+                    let end = start + *c.inner();
+                    if start >= range.start && end <= range.end {
+                        let s: Cow<S> = meta.submeta();
+                        merge_into::<T, S>(acc, s.into_owned());
+                    } else if end > range.start {
+                        self.query_node(child_location.relative(location.depth),
+                                        start,
+                                        range,
+                                        acc);
+                    }
+                    start = end;
+                }
+                Child::Leaf(ref t) => {
+                    if start >= range.start {
+                        merge_into::<T, S>(acc, S::from_t(t));
+                    }
+                    start += 1;
                 }
             }
         }
@@ -268,6 +833,123 @@ impl<T, M> Collection<T, M>
     }
 }
 
+impl<T, M> Collection<T, M>
+    where T: Val,
+          M: Meta<T> + SubMeta<Cardinality<usize>>
+{
+    /// Returns the number of elements in the Collection, read from the
+    /// metadata of the root node.
+    pub fn len(&self) -> usize {
+        match self.stash.get(self.root).meta() {
+            Some(meta) => {
+                let c: Cow<Cardinality<usize>> = (*meta).submeta();
+                *c.inner()
+            }
+            None => 0,
+        }
+    }
+}
+
+impl<T, M> Collection<T, M>
+    where T: Val,
+          M: Meta<T> + SubMeta<Distinct<T>>
+{
+    /// Returns an estimate of the number of distinct elements in the
+    /// Collection, read from the metadata of the root node.
+    pub fn distinct(&self) -> usize {
+        match self.stash.get(self.root).meta() {
+            Some(meta) => {
+                let d: Cow<Distinct<T>> = (*meta).submeta();
+                d.estimate()
+            }
+            None => 0,
+        }
+    }
+}
+
+impl<T, M> Collection<T, M>
+    where T: Val + Ord,
+          M: Meta<T>
+{
+    /// Returns the largest elements of the Collection in descending order,
+    /// as many as `A` keeps, read from the metadata of the root node.
+    pub fn top_k<A>(&self) -> Vec<T>
+        where A: Capacity<T = T>,
+              M: SubMeta<TopK<A>>
+    {
+        match self.stash.get(self.root).meta() {
+            Some(meta) => {
+                let top: Cow<TopK<A>> = (*meta).submeta();
+                top.inner().to_vec()
+            }
+            None => vec![],
+        }
+    }
+}
+
+// Copies the node at `location` in `from`, and everything below it, into
+// `into`, returning its new location
+fn compact_node<T, M>(from: &Stash<T, M>,
+                      location: Location<T, M>,
+                      into: &mut Stash<T, M>)
+                      -> Location<T, M>
+    where T: Val,
+          M: Meta<T>
+{
+    let mut node = Node::new();
+    for child in &from.get(location).children {
+        node.children.push_back(match *child {
+            Child::Node { location: child_location, ref meta } => {
+                let child_location = child_location.relative(location.depth);
+                Child::new_node(compact_node(from, child_location, into),
+                                meta.clone())
+            }
+            Child::Leaf(ref t) => Child::new_leaf(t.clone()),
+        });
+    }
+    into.put(node)
+}
+
+// Merges `s` into the metadata accumulated so far
+fn merge_into<T, S>(acc: &mut Option<S>, s: S)
+    where T: Val,
+          S: Meta<T>
+{
+    match *acc {
+        Some(ref mut acc) => acc.merge(&s, PhantomData),
+        None => *acc = Some(s),
+    }
+}
+
+// Appends `left` to the branch being constructed in `into`
+fn append_left<T, M>(into: &mut Option<Branch<T, M, End>>,
+                     left: Branch<T, M, End>,
+                     divisor: usize,
+                     stash: &mut Stash<T, M>)
+    where T: Val,
+          M: Meta<T>
+{
+    *into = Some(match into.take() {
+        Some(branch) => branch.concat(&left.reverse(stash), divisor, stash),
+        None => left,
+    })
+}
+
+// Appends everything from `rest` onwards to `into`
+fn append_rest<T, M>(into: &mut Option<Branch<T, M, End>>,
+                     rest: &Branch<T, M, Beginning>,
+                     divisor: usize,
+                     stash: &mut Stash<T, M>)
+    where T: Val,
+          M: Meta<T>
+{
+    *into = Some(match into.take() {
+        Some(branch) => branch.concat(rest, divisor, stash),
+        None => rest.reverse(stash),
+    })
+}
+
+/// Defines a collection type with the given metadata
 #[macro_export]
 macro_rules! collection {
     ($collection:ident<$t:ident>
@@ -327,3 +1009,457 @@ macro_rules! collection {
         pub type $collection<T> = Collection<T, self::col::CollectionMeta<T>>;
     )
 }
+
+#[cfg(test)]
+mod tests {
+    const LOTS: usize = 100_000;
+
+    use std::hash::Hash;
+
+    use meta::Meta;
+    use meta::cardinality::Cardinality;
+    use meta::checksum::CheckSum;
+    use ops::vector::VectorOps;
+    use tree::node::Child;
+    use tree::weight::Weight;
+
+    use super::{Collection, Violation};
+
+    collection!(Vector<T> {
+        cardinality: Cardinality<usize>,
+        checksum: CheckSum<u64>,
+    } where T: Hash);
+
+    #[test]
+    fn debug_tree() {
+        let mut vec = Vector::new();
+
+        assert_eq!(vec.debug_tree_using::<Cardinality<usize>>(), "");
+
+        for i in 0..1000 {
+            vec.push(i);
+        }
+
+        let tree = vec.debug_tree_using::<Cardinality<usize>>();
+        let leaves: Vec<usize> = tree.lines()
+            .filter(|line| line.contains("weight"))
+            .map(|line| line.trim().split(' ').next().unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(leaves, (0..1000).collect::<Vec<_>>());
+
+        // The top level nodes account for every element
+        let counted: usize = tree.lines()
+            .filter(|line| line.starts_with("loc"))
+            .map(|line| {
+                let start = line.find("Cardinality(").unwrap() + 12;
+                line[start..line.len() - 1].parse::<usize>().unwrap()
+            })
+            .sum();
+        let top_leaves = tree.lines()
+            .filter(|line| !line.starts_with(' ') && line.contains("weight"))
+            .count();
+        assert_eq!(counted + top_leaves, 1000);
+    }
+
+    #[test]
+    fn validate() {
+        let mut vec = Vector::new();
+
+        assert_eq!(vec.validate_using::<CheckSum<u64>>(), vec![]);
+
+        for i in 0..LOTS {
+            vec.push(i);
+        }
+        for i in 0..LOTS / 10 {
+            vec.insert(i * 7, i);
+            vec.remove(i * 3);
+        }
+        assert_eq!(vec.validate_using::<Cardinality<usize>>(), vec![]);
+        assert_eq!(vec.validate_using::<CheckSum<u64>>(), vec![]);
+
+        let mut sorted = Vector::from_sorted(0..LOTS);
+        assert_eq!(sorted.validate_using::<CheckSum<u64>>(), vec![]);
+
+        // Overwrite the metadata of a node with that of a single element
+        let mut corrupt = sorted.clone_mut();
+        let mut root = corrupt.root;
+        let mut stale = None;
+        for child in &mut corrupt.stash.get_mut(&mut root).children {
+            if let Child::Node { location, ref mut meta } = *child {
+                *meta = Meta::from_t(&0);
+                stale = Some(location);
+                break;
+            }
+        }
+        corrupt.root = root;
+        let stale = stale.unwrap().relative(root.depth);
+        assert_eq!(corrupt.validate_using::<CheckSum<u64>>(),
+                   vec![Violation::Meta {
+                            ofs: stale.ofs,
+                            depth: stale.depth,
+                        }]);
+
+        // Point a child at a node that does not exist
+        let mut dangling = sorted.clone_mut();
+        let mut root = dangling.root;
+        for child in &mut dangling.stash.get_mut(&mut root).children {
+            if let Child::Node { ref mut location, .. } = *child {
+                location.ofs = LOTS;
+            }
+        }
+        dangling.root = root;
+        let violations = dangling.validate_using::<CheckSum<u64>>();
+        assert!(!violations.is_empty());
+        for violation in violations {
+            match violation {
+                Violation::Missing { ofs, .. } => assert_eq!(ofs, LOTS),
+                _ => panic!("unexpected {:?}", violation),
+            }
+        }
+
+        // Removing the element the first node splits on joins it with
+        // the next one
+        let mut unsplit = Vector::from_sorted(0..LOTS);
+        let split = (0..LOTS).position(|i| i.weight() / 2 > 0).unwrap();
+        unsplit.remove(split);
+        assert_eq!(unsplit.validate_using::<CheckSum<u64>>(), vec![]);
+    }
+
+    #[test]
+    fn stats() {
+        let mut vec = Vector::new();
+
+        let empty = vec.stats();
+        assert_eq!((empty.depth, empty.nodes, empty.leaves), (0, 0, 0));
+        assert_eq!(empty.fill(), 0.0);
+
+        for i in 0..LOTS {
+            vec.push(i);
+        }
+
+        let stats = vec.stats();
+        assert_eq!(stats.leaves, LOTS);
+        assert!(stats.depth > 1);
+        assert!(stats.nodes < LOTS);
+        assert!(stats.bytes > stats.nodes);
+        // With a divisor of 2, every fourth element splits a node
+        assert!(stats.fill() > 2.0 && stats.fill() < 8.0);
+
+        let mut small = Vector::new();
+        small.push(0);
+        let stats = small.stats();
+        assert_eq!((stats.depth, stats.nodes, stats.leaves), (1, 1, 1));
+        assert_eq!(stats.fill(), 1.0);
+    }
+
+    #[test]
+    fn transaction() {
+        let mut vec = Vector::new();
+
+        for i in 0..LOTS {
+            vec.push(i);
+        }
+
+        let res: Result<(), usize> = vec.transaction(|vec| {
+            vec.push(LOTS);
+            vec.remove(0);
+            vec.set(0, 0);
+            Err(vec.len())
+        });
+        assert_eq!(res, Err(LOTS));
+        assert_eq!(vec.len(), LOTS);
+        for i in 0..LOTS {
+            assert_eq!(vec.get(i), Some(&i));
+        }
+
+        let res: Result<usize, ()> = vec.transaction(|vec| {
+            vec.push(LOTS);
+            vec.remove(0);
+            Ok(vec.len())
+        });
+        assert_eq!(res, Ok(LOTS));
+        for i in 0..LOTS {
+            assert_eq!(vec.get(i), Some(&(i + 1)));
+        }
+
+        // Snapshots taken in a failed transaction are discarded, along
+        // with the removal of existing ones
+        vec.snapshot("before");
+        let res: Result<(), ()> = vec.transaction(|vec| {
+            assert!(vec.remove_snapshot("before"));
+            vec.push(0);
+            vec.snapshot("failed");
+            Err(())
+        });
+        assert_eq!(res, Err(()));
+        assert!(!vec.restore_snapshot("failed"));
+        assert!(vec.restore_snapshot("before"));
+        assert_eq!(vec.len(), LOTS);
+
+        // And kept in a committed one
+        let res: Result<(), ()> = vec.transaction(|vec| {
+            vec.push(0);
+            vec.snapshot("committed");
+            vec.pop();
+            assert!(vec.restore_snapshot("before"));
+            assert!(vec.remove_snapshot("before"));
+            Ok(())
+        });
+        assert_eq!(res, Ok(()));
+        assert!(!vec.restore_snapshot("before"));
+        assert!(vec.restore_snapshot("committed"));
+        assert_eq!(vec.len(), LOTS + 1);
+        assert_eq!(vec.get(LOTS), Some(&0));
+    }
+
+    #[test]
+    fn snapshot() {
+        let mut vec = Vector::new();
+
+        for i in 0..LOTS {
+            vec.push(i);
+        }
+
+        vec.snapshot("full");
+        for _ in 0..LOTS / 2 {
+            vec.pop();
+        }
+        vec.snapshot("half");
+        vec.push(0);
+
+        assert!(vec.restore_snapshot("full"));
+        assert_eq!(vec.len(), LOTS);
+        assert_eq!(vec.get(LOTS - 1), Some(&(LOTS - 1)));
+
+        vec.set(0, 1);
+        assert!(vec.restore_snapshot("half"));
+        assert_eq!(vec.len(), LOTS / 2);
+        assert!(vec.restore_snapshot("full"));
+        assert_eq!(vec.get(0), Some(&0));
+
+        assert!(vec.remove_snapshot("half"));
+        assert!(!vec.remove_snapshot("half"));
+        assert!(!vec.restore_snapshot("half"));
+        assert_eq!(vec.len(), LOTS);
+
+        // Snapshots survive transactions
+        let res: Result<(), ()> = vec.transaction(|vec| {
+            vec.push(LOTS);
+            Ok(())
+        });
+        assert_eq!(res, Ok(()));
+        assert!(vec.restore_snapshot("full"));
+        assert_eq!(vec.len(), LOTS);
+
+        // And draining
+        assert_eq!(vec.drain().count(), LOTS);
+        assert!(vec.is_empty());
+        assert!(vec.restore_snapshot("full"));
+        assert_eq!(vec.len(), LOTS);
+    }
+
+    #[test]
+    fn compact() {
+        let mut vec = Vector::new();
+
+        assert_eq!(vec.compact(), 0);
+
+        for i in 0..LOTS {
+            vec.push(i);
+        }
+        let mut copy = vec.clone_mut();
+        for i in 0..LOTS / 10 {
+            vec.set(i * 10, 0);
+        }
+
+        let nodes = vec.stats().nodes;
+        let dropped = vec.compact();
+        assert!(dropped > 0);
+        assert_eq!(vec.stats().nodes, nodes);
+        assert_eq!(vec.compact(), 0);
+        assert_eq!(vec.validate_using::<CheckSum<u64>>(), vec![]);
+
+        for i in 0..LOTS {
+            if i % 10 == 0 {
+                assert_eq!(vec.get(i), Some(&0));
+            } else {
+                assert_eq!(vec.get(i), Some(&i));
+            }
+            assert_eq!(copy.get(i), Some(&i));
+        }
+
+        // Still usable, and independent of the copy
+        vec.push(LOTS);
+        copy.compact();
+        assert_eq!(vec.len(), LOTS + 1);
+        assert_eq!(copy.len(), LOTS);
+    }
+
+    #[test]
+    fn export_import() {
+        use std::io::{self, Read, Write};
+
+        fn encode<W: Write>(t: &u64, w: &mut W) -> io::Result<()> {
+            w.write_all(&t.to_le_bytes())
+        }
+
+        fn decode<R: Read>(r: &mut R) -> io::Result<u64> {
+            let mut bytes = [0; 8];
+            r.read_exact(&mut bytes)?;
+            Ok(u64::from_le_bytes(bytes))
+        }
+
+        let mut vec = Vector::new();
+
+        for i in 0..LOTS as u64 {
+            vec.push(i * 3);
+        }
+        vec.remove(7);
+
+        let mut bytes = vec![];
+        vec.export(&mut bytes, encode).unwrap();
+        assert_eq!(bytes.len(), 13 + (LOTS - 1) * 8);
+
+        let imported = Vector::import(&mut &bytes[..], decode).unwrap();
+        assert!(imported == vec);
+        assert_eq!(imported.len(), LOTS - 1);
+        assert_eq!(imported.validate_using::<CheckSum<u64>>(), vec![]);
+
+        let mut empty = vec![];
+        Vector::<u64>::new().export(&mut empty, encode).unwrap();
+        let imported = Vector::import(&mut &empty[..], decode).unwrap();
+        assert!(imported.is_empty());
+
+        // Not an exported collection
+        bytes[0] = b'x';
+        assert!(Vector::import(&mut &bytes[..], decode).is_err());
+        // Cut short
+        assert!(Vector::import(&mut &empty[..10], decode).is_err());
+    }
+
+    #[test]
+    fn clone_into() {
+        let mut vec = Vector::new();
+
+        for i in 0..LOTS {
+            vec.push(i);
+        }
+        vec.remove(3);
+        let shared = vec.clone_mut();
+
+        let mut copy = Vector::new();
+        let before = copy.stash.len();
+        vec.clone_into(&mut copy);
+        assert!(copy.deep_eq(&vec));
+        assert_eq!(copy.stash.len(), before + copy.stats().nodes);
+        assert_eq!(copy.validate_using::<CheckSum<u64>>(), vec![]);
+
+        // Into a collection holding other nodes, and snapshots
+        let mut copy = Vector::new();
+        copy.push(LOTS);
+        copy.snapshot("other");
+        let before = copy.stash.len();
+        vec.clone_into(&mut copy);
+        assert_eq!(copy.stash.len(), before + copy.stats().nodes);
+        assert!(copy.deep_eq(&vec));
+
+        copy.push(LOTS);
+        vec.set(0, 1);
+        assert_eq!(copy.get(0), Some(&0));
+        assert_eq!(copy.len(), LOTS);
+        assert_eq!(vec.len(), LOTS - 1);
+        assert!(shared.iter().eq(copy.iter().take(LOTS - 1)));
+
+        assert!(copy.restore_snapshot("other"));
+        assert_eq!(copy.len(), 1);
+    }
+
+    #[test]
+    fn memory_usage() {
+        let mut vec = Vector::new();
+        let empty = vec.memory_usage();
+        assert_eq!(empty.shared, 0);
+
+        for i in 0..LOTS {
+            vec.push(i);
+        }
+        let usage = vec.memory_usage();
+        assert!(usage.owned > empty.owned);
+        assert!(usage.owned >= vec.stats().bytes);
+        assert_eq!(usage.shared, 0);
+
+        // All nodes move to a shared layer
+        let copy = vec.clone_mut();
+        let after = vec.memory_usage();
+        assert_eq!(after.owned, 0);
+        assert_eq!(after.total(), usage.total());
+        assert_eq!(copy.memory_usage(), after);
+
+        vec.set(0, 1);
+        assert!(vec.memory_usage().owned > 0);
+        assert_eq!(vec.memory_usage().shared, after.shared);
+
+        vec.compact();
+        assert_eq!(vec.memory_usage().shared, 0);
+        assert!(vec.memory_usage().total() < usage.total());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        use serde::Deserialize;
+        use serde::de::IntoDeserializer;
+        use serde::de::value::{Error, U32Deserializer};
+        use serde_test::{assert_ser_tokens, Token};
+
+        use super::deserialize_sorted;
+
+        let mut vec = Vector::new();
+
+        assert_ser_tokens(&vec, &[Token::Seq { len: None }, Token::SeqEnd]);
+
+        for i in 0..3u32 {
+            vec.push(i);
+        }
+        assert_ser_tokens(&vec,
+                          &[Token::Seq { len: None },
+                            Token::U32(0),
+                            Token::U32(1),
+                            Token::U32(2),
+                            Token::SeqEnd]);
+
+        let elements: Vec<u32> = vec![4, 2, 4, 1];
+        let de = elements.into_deserializer();
+        let vec: Result<Vector<u32>, Error> = Vector::deserialize(de);
+        let vec = vec.unwrap();
+        assert_eq!(vec.len(), 4);
+        assert_eq!(vec.get(2), Some(&4));
+
+        // Not a sequence
+        let de: U32Deserializer<Error> = 7u32.into_deserializer();
+        assert!(Vector::<u32>::deserialize(de).is_err());
+
+        // Only strictly increasing elements, for sets
+        let elements: Vec<u32> = vec![1, 2, 4];
+        let de = elements.into_deserializer();
+        let vec: Result<Vector<u32>, Error> = deserialize_sorted(de);
+        assert!(vec.unwrap().iter().cloned().eq(vec![1, 2, 4]));
+        for elements in [vec![4, 2], vec![1, 2, 2]] {
+            let de = elements.into_deserializer();
+            let vec: Result<Vector<u32>, Error> = deserialize_sorted(de);
+            assert!(vec.is_err());
+        }
+
+        // The rebuilt tree is the same as the original one
+        let mut vec = Vector::new();
+        for i in 0..LOTS {
+            vec.push(i * 7 % 1000);
+        }
+        let elements: Vec<usize> = vec.iter().cloned().collect();
+        let de = elements.into_deserializer();
+        let copy: Result<Vector<usize>, Error> = Vector::deserialize(de);
+        let copy = copy.unwrap();
+        assert!(copy == vec);
+        assert_eq!(copy.validate_using::<CheckSum<u64>>(), vec![]);
+    }
+}
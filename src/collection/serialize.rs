@@ -0,0 +1,85 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::{self, Visitor, SeqAccess};
+use serde::ser::SerializeSeq;
+
+use Val;
+use collection::Collection;
+use meta::Meta;
+
+impl<T, M> Serialize for Collection<T, M>
+    where T: Val + Serialize,
+          M: Meta<T>
+{
+    /// Serializes the collection as the sequence of its elements, in order
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let mut seq = serializer.serialize_seq(None)?;
+        for t in self.iter() {
+            seq.serialize_element(t)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, T, M> Deserialize<'de> for Collection<T, M>
+    where T: Val + Deserialize<'de>,
+          M: Meta<T>
+{
+    /// Deserializes a sequence of elements written by `serialize`, and
+    /// rebuilds the tree with `from_sorted`.
+    ///
+    /// As with `import`, the elements have to be in the order of the
+    /// collection, which is not checked. Sets read from untrusted input
+    /// should use `deserialize_sorted` instead.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        deserializer.deserialize_seq(ElementsVisitor(PhantomData))
+    }
+}
+
+/// Deserializes a set, failing unless its elements are in strictly
+/// increasing order.
+///
+/// Meant for `#[serde(deserialize_with = "collection::deserialize_sorted")]`
+/// on sets read from untrusted input: out of order elements would build a
+/// tree where lookups silently miss.
+pub fn deserialize_sorted<'de, D, T, M>(deserializer: D)
+                                        -> Result<Collection<T, M>, D::Error>
+    where D: Deserializer<'de>,
+          T: Val + Ord + Deserialize<'de>,
+          M: Meta<T>
+{
+    let elements: Vec<T> = Vec::deserialize(deserializer)?;
+    if elements.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(de::Error::custom("elements out of order"));
+    }
+    Ok(Collection::from_sorted(elements))
+}
+
+struct ElementsVisitor<T, M>(PhantomData<(T, M)>);
+
+impl<'de, T, M> Visitor<'de> for ElementsVisitor<T, M>
+    where T: Val + Deserialize<'de>,
+          M: Meta<T>
+{
+    type Value = Collection<T, M>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence of elements")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where A: SeqAccess<'de>
+    {
+        let mut elements = vec![];
+        while let Some(t) = seq.next_element()? {
+            elements.push(t);
+        }
+        Ok(Collection::from_sorted(elements))
+    }
+}
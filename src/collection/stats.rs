@@ -0,0 +1,111 @@
+use std::mem;
+
+use Val;
+use collection::Collection;
+use meta::Meta;
+use stash::Location;
+use tree::node::{Node, Child};
+
+/// Statistics on the shape of the tree of a Collection, returned by
+/// `Collection::stats`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    /// The number of nodes from the root down to the leaves
+    pub depth: usize,
+    /// The number of nodes in the tree
+    pub nodes: usize,
+    /// The number of elements in the tree
+    pub leaves: usize,
+    /// Estimate of the number of bytes taken by the nodes
+    pub bytes: usize,
+}
+
+/// The memory held by the stash of a Collection, returned by
+/// `Collection::memory_usage`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryUsage {
+    /// Bytes taken by nodes created or changed since the last `clone_mut`,
+    /// reachable or not
+    pub owned: usize,
+    /// Bytes taken by nodes shared with clones of the Collection
+    pub shared: usize,
+}
+
+impl MemoryUsage {
+    /// All the bytes held, owned and shared
+    pub fn total(&self) -> usize {
+        self.owned + self.shared
+    }
+}
+
+impl Stats {
+    /// The average number of children of a node
+    pub fn fill(&self) -> f64 {
+        if self.nodes == 0 {
+            0.0
+        } else {
+            // Every node but the root is the child of another one
+            (self.leaves + self.nodes - 1) as f64 / self.nodes as f64
+        }
+    }
+}
+
+impl<T, M> Collection<T, M>
+    where T: Val,
+          M: Meta<T>
+{
+    /// Walks the entire tree, collecting statistics on its shape.
+    ///
+    /// Nodes shared with other collections are counted as well.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            depth: 0,
+            nodes: 0,
+            leaves: 0,
+            bytes: 0,
+        };
+        if !self.stash.get(self.root).children.is_empty() {
+            self.stats_node(self.root, 1, &mut stats);
+        }
+        stats
+    }
+
+    /// Reports the bytes allocated for the nodes in the stash of the
+    /// Collection, not counting what the elements point to.
+    ///
+    /// Unlike `stats`, this includes nodes no longer reachable from the
+    /// root, which `compact` drops. Shared nodes are counted by every
+    /// Collection sharing them.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let (owned, shared) = self.stash.bytes();
+        MemoryUsage {
+            owned: owned,
+            shared: shared,
+        }
+    }
+
+    // Adds the node at `location`, `depth` nodes below the root
+    // counting itself, and everything below it to `stats`.
+    fn stats_node(&self,
+                  location: Location<T, M>,
+                  depth: usize,
+                  stats: &mut Stats) {
+        let node = self.stash.get(location);
+        stats.nodes += 1;
+        stats.bytes += mem::size_of::<Node<T, M>>() +
+                       node.children.len() * mem::size_of::<Child<T, M>>();
+        if depth > stats.depth {
+            stats.depth = depth;
+        }
+        for child in &node.children {
+            match *child {
+                Child::Node { location: child_location, .. } => {
+                    self.stats_node(child_location.relative(location.depth),
+                                    depth + 1,
+                                    stats);
+                }
+                Child::Leaf(_) => stats.leaves += 1,
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::borrow::Cow;
+
+use Val;
+use collection::{Collection, merge_into};
+use meta::{Meta, SubMeta};
+use stash::Location;
+use tree::node::Child;
+
+/// A violation of the invariants of the tree, found by
+/// `Collection::validate_using`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// A child location does not resolve to a node in the stash
+    Missing {
+        /// Offset of the location
+        ofs: usize,
+        /// Depth of the location
+        depth: usize,
+    },
+    /// The metadata stored for a node differs from the metadata
+    /// recomputed from the elements below it
+    Meta {
+        /// Offset of the node
+        ofs: usize,
+        /// Depth of the node
+        depth: usize,
+    },
+    /// A child of a node ends with an element whose weight does not
+    /// match its position, the node should have been split after it, or
+    /// continued past it
+    Weight {
+        /// Offset of the node
+        ofs: usize,
+        /// Depth of the node
+        depth: usize,
+        /// Index of the child in the node
+        index: usize,
+    },
+    /// A node has leaves below it at different heights
+    Height {
+        /// Offset of the node
+        ofs: usize,
+        /// Depth of the node
+        depth: usize,
+    },
+}
+
+impl<T, M> Collection<T, M>
+    where T: Val,
+          M: Meta<T>
+{
+    /// Walks the entire tree, checking that every child location resolves
+    /// in the stash, that every node splits where the weights of its
+    /// elements say it should, and that the stored `S` metadata of every
+    /// node matches the metadata recomputed from its elements.
+    ///
+    /// Returns all the violations found, an empty `Vec` for a valid tree.
+    pub fn validate_using<S>(&self) -> Vec<Violation>
+        where S: Meta<T> + PartialEq,
+              M: SubMeta<S>
+    {
+        let mut violations = vec![];
+        if self.stash.contains(self.root) {
+            let height = self.height();
+            self.validate_node::<S>(self.root,
+                                    height,
+                                    true,
+                                    &mut violations);
+        } else {
+            violations.push(Violation::Missing {
+                ofs: self.root.ofs,
+                depth: self.root.depth,
+            });
+        }
+        violations
+    }
+
+    // The number of nodes between the root and the first leaf
+    fn height(&self) -> usize {
+        let mut location = self.root;
+        let mut height = 0;
+        while self.stash.contains(location) {
+            match self.stash.get(location).children.front() {
+                Some(&Child::Node { location: child_location, .. }) => {
+                    location = child_location.relative(location.depth);
+                    height += 1;
+                }
+                _ => break,
+            }
+        }
+        height
+    }
+
+    // Validates the node at `location`, `height` nodes above the leaves,
+    // and everything below it. `last` is true if the node ends with the
+    // last element of the collection.
+    //
+    // Returns the recomputed metadata of the node, along with the weight
+    // of its last element.
+    fn validate_node<S>(&self,
+                        location: Location<T, M>,
+                        height: usize,
+                        last: bool,
+                        violations: &mut Vec<Violation>)
+                        -> (Option<S>, Option<usize>)
+        where S: Meta<T> + PartialEq,
+              M: SubMeta<S>
+    {
+        let node = self.stash.get(location);
+        let len = node.children.len();
+        let mut acc = None;
+        let mut weight = None;
+
+        for (i, child) in node.children.iter().enumerate() {
+            let last_child = last && i + 1 == len;
+            match *child {
+                Child::Node { location: child_location, ref meta } => {
+                    let child_location =
+                        child_location.relative(location.depth);
+                    if !self.stash.contains(child_location) {
+                        violations.push(Violation::Missing {
+                            ofs: child_location.ofs,
+                            depth: child_location.depth,
+                        });
+                        weight = None;
+                        continue;
+                    }
+                    if height == 0 {
+                        violations.push(Violation::Height {
+                            ofs: location.ofs,
+                            depth: location.depth,
+                        });
+                        continue;
+                    }
+                    let (recomputed, w) = self.validate_node::<S>(
+                        child_location, height - 1, last_child, violations);
+                    let stored: Cow<S> = meta.submeta();
+                    if recomputed.as_ref() != Some(&*stored) {
+                        violations.push(Violation::Meta {
+                            ofs: child_location.ofs,
+                            depth: child_location.depth,
+                        });
+                    }
+                    merge_into::<T, S>(&mut acc, stored.into_owned());
+                    weight = w;
+                }
+                Child::Leaf(ref t) => {
+                    if height != 0 {
+                        violations.push(Violation::Height {
+                            ofs: location.ofs,
+                            depth: location.depth,
+                        });
+                    }
+                    merge_into::<T, S>(&mut acc, S::from_t(t));
+                    weight = Some(t.weight() / self.divisor);
+                }
+            }
+
+            // Every child but the last ends with an element too light to
+            // split the node, the last one with an element heavy enough,
+            // unless it is the last element of the collection.
+            if let Some(w) = weight {
+                let splits = w > height;
+                if splits != (i + 1 == len) && !last_child {
+                    violations.push(Violation::Weight {
+                        ofs: location.ofs,
+                        depth: location.depth,
+                        index: i,
+                    });
+                }
+            }
+        }
+        (acc, weight)
+    }
+}
+
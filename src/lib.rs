@@ -5,14 +5,21 @@
 mod collection;
 
 extern crate seahash;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_test;
 #[macro_use]
 extern crate trait_group;
 
 mod stash;
 mod tree;
+#[cfg(feature = "visualize")]
 mod html;
+mod viz;
 mod meta;
 mod ops;
+mod proof;
 
 use tree::weight::Weight;
 
@@ -22,13 +29,39 @@ trait_group! {
     pub trait Val: Weight + Clone
 }
 
-pub use collection::Collection;
+pub use collection::{Collection, Stats, MemoryUsage, Violation};
+#[cfg(feature = "serde")]
+pub use collection::deserialize_sorted;
+pub use proof::{Proof, RangeProof, ProofDigest};
 
-pub use ops::vector::VectorOps;
-pub use ops::map::{MapOps, MapOpsKeySum};
-pub use ops::set::{SetOps, SetOpsCheckSum};
+pub use ops::vector::{VectorOps, VectorOpsMax, OutOfBounds, IndexedIter,
+                      Chunks, VectorSlice};
+pub use ops::map::{MapOps, MapOpsKeySum, MapOpsCardinality, MapOpsCheckSum,
+                   MapOpsPrefix, MapOpsBloom, MapOpsValueMax, MapChange};
+pub use ops::set::{SetOps, SetOpsCheckSum, SetOpsCardinality};
+pub use ops::multiset::{MultiSetOps, MultiSetOpsMultiplicity, Counted};
+pub use ops::multimap::MultiMapOps;
+pub use ops::union_view::UnionView;
+pub use ops::ord_by::{OrdBy, By, Reverse};
+pub use ops::secondary_index::SecondaryIndex;
+pub use ops::interval::IntervalOps;
+pub use ops::versioned::VersionOps;
+pub use ops::history::History;
 
 pub use meta::Meta;
 pub use meta::Max;
-pub use meta::CheckSum;
+pub use meta::Min;
+pub use meta::Bounds;
+pub use meta::Bloom;
+pub use meta::Distinct;
+pub use meta::{Histogram, Bucketed, Buckets};
+pub use meta::{TopK, Capacity};
+pub use meta::ValueMax;
+pub use meta::{Interval, IntervalMax};
+pub use meta::{CheckSum, CheckSumWith, Digest};
 pub use meta::Key;
+pub use meta::{Aggregate, MapMeta};
+pub use meta::Multiplicity;
+pub use meta::Prefix;
+pub use meta::{Sum, Summed, Mean, ToF64};
+pub use meta::{Version, Versioned};
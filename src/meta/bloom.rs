@@ -0,0 +1,62 @@
+use std::marker::PhantomData;
+
+use Val;
+use meta::Meta;
+use meta::key::Keyed;
+
+const WORDS: usize = 4;
+const BITS: u64 = (WORDS * 64) as u64;
+const HASHES: usize = 3;
+
+/// A bloom filter of the keys in a subtree, K is usually `T::Key` where
+/// `T: Keyed`
+///
+/// Can tell for sure that a key is not in the subtree, without looking
+/// at the nodes below.
+#[derive(Clone, PartialEq)]
+pub struct Bloom<K> {
+    words: [u64; WORDS],
+    _k: PhantomData<K>,
+}
+
+impl<K> Bloom<K>
+    where K: Val
+{
+    // The bits set for key, taken from different parts of its hash
+    fn bits(key: &K) -> [u64; HASHES] {
+        let hash = key.weight_hash();
+        let mut bits = [0; HASHES];
+        for (i, bit) in bits.iter_mut().enumerate() {
+            *bit = (hash >> (i * 16)) % BITS;
+        }
+        bits
+    }
+
+    /// Might `key` be in this subtree?
+    pub fn may_contain(&self, key: &K) -> bool {
+        Self::bits(key).iter().all(|bit| {
+            self.words[(bit / 64) as usize] & (1 << (bit % 64)) != 0
+        })
+    }
+}
+
+impl<T> Meta<T> for Bloom<T::Key>
+    where T: Val + Keyed
+{
+    fn from_t(t: &T) -> Self {
+        let mut words = [0; WORDS];
+        for bit in Self::bits(t.key()).iter() {
+            words[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        Bloom {
+            words: words,
+            _k: PhantomData,
+        }
+    }
+
+    fn merge(&mut self, other: &Self, _p: PhantomData<T>) {
+        for (a, b) in self.words.iter_mut().zip(other.words.iter()) {
+            *a |= *b;
+        }
+    }
+}
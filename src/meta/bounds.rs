@@ -0,0 +1,72 @@
+use Val;
+use std::marker::PhantomData;
+use std::borrow::Cow;
+use meta::{Meta, Select, Selection};
+
+/// Metadata for both the minimum and maximum `T` in subtree.
+///
+/// As a search, selects the first element within the bounds, or where
+/// it would have been, in a collection ordered by `T`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Bounds<T> {
+    min: T,
+    max: T,
+}
+
+impl<T> Bounds<T>
+    where T: Val + Ord
+{
+    /// Constructs the bounds `min..=max`
+    pub fn new(min: &T, max: &T) -> Self {
+        Bounds {
+            min: min.clone(),
+            max: max.clone(),
+        }
+    }
+
+    /// Returns the lower bound
+    pub fn min(&self) -> &T {
+        &self.min
+    }
+
+    /// Returns the upper bound
+    pub fn max(&self) -> &T {
+        &self.max
+    }
+
+    /// Do these bounds overlap with `other`?
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.min <= other.max && other.min <= self.max
+    }
+}
+
+impl<T> Meta<T> for Bounds<T>
+    where T: Val + Ord
+{
+    fn from_t(t: &T) -> Self {
+        Bounds::new(t, t)
+    }
+
+    fn merge(&mut self, other: &Self, _t: PhantomData<T>) {
+        if self.min > other.min {
+            self.min = other.min.clone()
+        }
+        if self.max < other.max {
+            self.max = other.max.clone()
+        }
+    }
+}
+
+impl<T> Select<T> for Bounds<T>
+    where T: Val + Ord
+{
+    fn select(&mut self, other: Cow<Self>) -> Selection {
+        if other.max < self.min {
+            Selection::Miss
+        } else if self.overlaps(&other) {
+            Selection::Hit
+        } else {
+            Selection::Between
+        }
+    }
+}
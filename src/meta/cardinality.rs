@@ -4,7 +4,7 @@ use std::borrow::Cow;
 use Val;
 use meta::{Meta, Select, Selection};
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct Cardinality<T>(T);
 
 impl<T> Cardinality<T>
@@ -19,27 +19,36 @@ impl<T> Cardinality<T>
     }
 }
 
-impl<T> Meta<T> for Cardinality<usize>
-    where T: Val
-{
-    fn from_t(_: &T) -> Self {
-        Cardinality(1)
-    }
+// Cardinality can be counted in `usize`, or in a fixed width unsigned
+// integer, for a representation that does not depend on the platform.
+// Narrower ones would overflow, `u16` at 65 536 elements and `u8` at 256.
+macro_rules! cardinality {
+    ($($int:ty),*) => ($(
+        impl<T> Meta<T> for Cardinality<$int>
+            where T: Val
+        {
+            fn from_t(_: &T) -> Self {
+                Cardinality(1)
+            }
 
-    fn merge(&mut self, other: &Self, _p: PhantomData<T>) {
-        self.0 += other.0;
-    }
-}
+            fn merge(&mut self, other: &Self, _p: PhantomData<T>) {
+                self.0 += other.0;
+            }
+        }
 
-impl<T> Select<T> for Cardinality<usize>
-    where T: Val
-{
-    fn select(&mut self, other: Cow<Self>) -> Selection {
-        if self.0 < other.0 {
-            Selection::Hit
-        } else {
-            self.0 -= other.0;
-            Selection::Miss
+        impl<T> Select<T> for Cardinality<$int>
+            where T: Val
+        {
+            fn select(&mut self, other: Cow<Self>) -> Selection {
+                if self.0 < other.0 {
+                    Selection::Hit
+                } else {
+                    self.0 -= other.0;
+                    Selection::Miss
+                }
+            }
         }
-    }
+    )*)
 }
+
+cardinality!(usize, u32, u64, u128);
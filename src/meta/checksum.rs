@@ -1,6 +1,8 @@
 use seahash::SeaHasher;
 
+use std::cmp::Ordering;
 use std::marker::PhantomData;
+use std::ptr;
 use std::hash::{Hash, Hasher};
 
 use Val;
@@ -19,6 +21,70 @@ impl<T> CheckSum<T> {
     }
 }
 
+/// Combines two checksums, in order.
+///
+/// Unlike simple arithmetic combinations, every bit of both inputs
+/// affects the result, and `combine(a, b) != combine(b, a)`.
+pub fn combine(a: u64, b: u64) -> u64 {
+    combine_with::<SeaHasher>(a, b)
+}
+
+/// Combines two checksums, in order, using the hasher `H`
+pub fn combine_with<H>(a: u64, b: u64) -> u64
+    where H: Hasher + Default
+{
+    let mut hasher = H::default();
+    hasher.write_u64(a);
+    hasher.write_u64(b);
+    hasher.finish()
+}
+
+/// A checksum computed with the hasher `H`, rather than `SeaHasher`.
+///
+/// With a cryptographic hasher, checksum collisions can not be forced by
+/// choosing the elements. Compare such collections with `eq_using`, and
+/// take unions with `union_using`.
+///
+/// Every hasher is made with `H::default()`, so it can not carry a secret
+/// key of its own; a key built into `H` is the same for every collection.
+pub struct CheckSumWith<H> {
+    sum: u64,
+    _h: PhantomData<H>,
+}
+
+impl<H> Clone for CheckSumWith<H> {
+    fn clone(&self) -> Self {
+        CheckSumWith {
+            sum: self.sum,
+            _h: PhantomData,
+        }
+    }
+}
+
+impl<H> PartialEq for CheckSumWith<H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.sum == other.sum
+    }
+}
+
+impl<T, H> Meta<T> for CheckSumWith<H>
+    where T: Val + Hash,
+          H: Hasher + Default
+{
+    fn from_t(t: &T) -> Self {
+        let mut hasher = H::default();
+        t.hash(&mut hasher);
+        CheckSumWith {
+            sum: hasher.finish(),
+            _h: PhantomData,
+        }
+    }
+
+    fn merge(&mut self, other: &Self, _p: PhantomData<T>) {
+        self.sum = combine_with::<H>(self.sum, other.sum);
+    }
+}
+
 impl<T> Meta<T> for CheckSum<u64>
     where T: Val + Hash
 {
@@ -29,11 +95,9 @@ impl<T> Meta<T> for CheckSum<u64>
     }
 
     fn merge(&mut self, other: &Self, _p: PhantomData<T>) {
-        // `(a ^ b) + a` does not commute! Which means checksum
+        // `combine` does not commute! Which means checksum
         // is order-dependant
-        let a = self.0;
-        let b = other.0;
-        self.0 = (a ^ b).wrapping_add(a);
+        self.0 = combine(self.0, other.0);
     }
 }
 
@@ -42,6 +106,63 @@ impl<T, M> PartialEq for Collection<T, M>
           M: Meta<T> + SubMeta<CheckSum<u64>>
 {
     fn eq(&self, other: &Self) -> bool {
-        self.stash.get(self.root) == other.stash.get(other.root)
+        self.eq_using::<CheckSum<u64>>(other)
+    }
+}
+
+impl<T, M> Eq for Collection<T, M>
+    where T: Val,
+          M: Meta<T> + SubMeta<CheckSum<u64>>
+{
+}
+
+impl<T, M> PartialOrd for Collection<T, M>
+    where T: Val + Ord,
+          M: Meta<T> + SubMeta<CheckSum<u64>>
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, M> Ord for Collection<T, M>
+    where T: Val + Ord,
+          M: Meta<T> + SubMeta<CheckSum<u64>>
+{
+    /// Compares the elements of the collections lexicographically, in
+    /// order. Collections with equal checksums are equal, without looking
+    /// at their elements.
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self == other {
+            Ordering::Equal
+        } else {
+            self.iter().cmp(other.iter())
+        }
+    }
+}
+
+impl<T, M> Collection<T, M>
+    where T: Val,
+          M: Meta<T>
+{
+    /// Compares two collections by the checksum metadata `E`
+    pub fn eq_using<E>(&self, other: &Self) -> bool
+        where E: Clone + PartialEq,
+              M: SubMeta<E>
+    {
+        self.stash.get(self.root).eq_using::<E>(other.stash.get(other.root))
+    }
+
+    /// Compares two collections element by element.
+    ///
+    /// Unlike `==` and `eq_using`, this can not be fooled by colliding
+    /// checksums, but takes time linear in the size of the collections.
+    pub fn deep_eq(&self, other: &Self) -> bool
+        where T: PartialEq
+    {
+        let a = self.stash.get(self.root);
+        let b = other.stash.get(other.root);
+        // The very same root node, as after `clone_mut`
+        ptr::eq(a, b) || self.iter().eq(other.iter())
     }
 }
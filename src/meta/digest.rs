@@ -0,0 +1,63 @@
+use std::marker::PhantomData;
+use std::hash::{Hash, Hasher};
+
+use Val;
+use meta::Meta;
+use meta::checksum::combine_with;
+
+/// An order dependent checksum computed with the hasher `H`, that also
+/// commits to the number of elements in every subtree.
+///
+/// Proofs made with `Digest` prove the position of an element, not only
+/// that it is there.
+pub struct Digest<H> {
+    sum: u64,
+    count: usize,
+    _h: PhantomData<H>,
+}
+
+impl<H> Digest<H> {
+    /// Returns the number of elements in the subtree
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl<H> Clone for Digest<H> {
+    fn clone(&self) -> Self {
+        Digest {
+            sum: self.sum,
+            count: self.count,
+            _h: PhantomData,
+        }
+    }
+}
+
+impl<H> PartialEq for Digest<H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.sum == other.sum && self.count == other.count
+    }
+}
+
+impl<T, H> Meta<T> for Digest<H>
+    where T: Val + Hash,
+          H: Hasher + Default
+{
+    fn from_t(t: &T) -> Self {
+        let mut hasher = H::default();
+        t.hash(&mut hasher);
+        Digest {
+            sum: hasher.finish(),
+            count: 1,
+            _h: PhantomData,
+        }
+    }
+
+    fn merge(&mut self, other: &Self, _p: PhantomData<T>) {
+        self.sum = combine_with::<H>(combine_with::<H>(self.sum,
+                                                       self.count as u64),
+                                     combine_with::<H>(other.sum,
+                                                       other.count as u64));
+        self.count += other.count;
+    }
+}
@@ -0,0 +1,67 @@
+use std::marker::PhantomData;
+
+use Val;
+use meta::Meta;
+
+const INDEX_BITS: usize = 8;
+const REGISTERS: usize = 1 << INDEX_BITS;
+
+/// A HyperLogLog sketch of the elements in a subtree, giving an
+/// estimate of the number of distinct elements.
+#[derive(Clone)]
+pub struct Distinct<T> {
+    registers: [u8; REGISTERS],
+    _t: PhantomData<T>,
+}
+
+impl<T> Distinct<T> {
+    /// Returns the estimated number of distinct elements, typically
+    /// within a few percent of the exact count
+    pub fn estimate(&self) -> usize {
+        let m = REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+
+        let mut sum = 0.0;
+        let mut zeros = 0;
+        for &register in self.registers.iter() {
+            sum += 1.0 / (1u64 << register) as f64;
+            if register == 0 {
+                zeros += 1;
+            }
+        }
+
+        let estimate = alpha * m * m / sum;
+        // Linear counting is more accurate for small cardinalities
+        if estimate <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as usize
+        } else {
+            estimate.round() as usize
+        }
+    }
+}
+
+impl<T> Meta<T> for Distinct<T>
+    where T: Val
+{
+    fn from_t(t: &T) -> Self {
+        let hash = t.weight_hash();
+        let index = (hash as usize) & (REGISTERS - 1);
+        let rank = (hash >> INDEX_BITS).leading_zeros() as usize -
+                   INDEX_BITS + 1;
+
+        let mut registers = [0; REGISTERS];
+        registers[index] = rank as u8;
+        Distinct {
+            registers: registers,
+            _t: PhantomData,
+        }
+    }
+
+    fn merge(&mut self, other: &Self, _p: PhantomData<T>) {
+        for (a, b) in self.registers.iter_mut().zip(other.registers.iter()) {
+            if *a < *b {
+                *a = *b;
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::marker::PhantomData;
+
+use Val;
+use meta::Meta;
+
+/// This `T` falls into one of the buckets of a `Histogram`.
+pub trait Bucketed {
+    /// The index of the bucket for this element, elements past the last
+    /// bucket are counted in the last bucket
+    fn bucket(&self) -> usize;
+}
+
+/// Storage for the counts of a `Histogram`, implemented for arrays
+/// `[usize; N]` with N buckets.
+pub trait Buckets: Clone {
+    /// All buckets empty
+    fn empty() -> Self;
+    /// The count of each bucket
+    fn counts(&self) -> &[usize];
+    /// Mutable count of each bucket
+    fn counts_mut(&mut self) -> &mut [usize];
+}
+
+impl<const N: usize> Buckets for [usize; N] {
+    fn empty() -> Self {
+        [0; N]
+    }
+    fn counts(&self) -> &[usize] {
+        self
+    }
+    fn counts_mut(&mut self) -> &mut [usize] {
+        self
+    }
+}
+
+/// The number of elements in each bucket in a subtree, B is usually
+/// `[usize; N]`
+#[derive(Clone, PartialEq)]
+pub struct Histogram<B>(B);
+
+impl<B> Histogram<B>
+    where B: Buckets
+{
+    /// Returns the count of each bucket
+    pub fn counts(&self) -> &[usize] {
+        self.0.counts()
+    }
+
+    /// Returns the total number of elements counted
+    pub fn total(&self) -> usize {
+        self.counts().iter().sum()
+    }
+
+    /// Returns the index of the bucket containing the `p`th percentile,
+    /// or `None` if there are no elements
+    pub fn percentile(&self, p: f64) -> Option<usize> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let rank = (p / 100.0 * total as f64).ceil() as usize;
+        let mut seen = 0;
+        for (i, count) in self.counts().iter().enumerate() {
+            seen += *count;
+            if seen >= rank && seen > 0 {
+                return Some(i);
+            }
+        }
+        Some(self.counts().len() - 1)
+    }
+}
+
+impl<T, B> Meta<T> for Histogram<B>
+    where T: Val + Bucketed,
+          B: Buckets
+{
+    fn from_t(t: &T) -> Self {
+        let mut buckets = B::empty();
+        {
+            let counts = buckets.counts_mut();
+            let last = counts.len() - 1;
+            counts[if t.bucket() < last { t.bucket() } else { last }] += 1;
+        }
+        Histogram(buckets)
+    }
+
+    fn merge(&mut self, other: &Self, _p: PhantomData<T>) {
+        for (a, b) in self.0.counts_mut().iter_mut().zip(other.counts()) {
+            *a += *b;
+        }
+    }
+}
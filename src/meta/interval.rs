@@ -0,0 +1,77 @@
+use std::marker::PhantomData;
+use std::borrow::Cow;
+
+use Val;
+use meta::{Meta, Select, Selection};
+
+/// This `T` spans the points from `start` up to, but not including,
+/// `end`.
+pub trait Interval {
+    /// The type of the end points
+    type Point: Val + Ord;
+
+    /// The first point of the interval
+    fn start(&self) -> &Self::Point;
+    /// The first point after the interval
+    fn end(&self) -> &Self::Point;
+}
+
+impl<P> Interval for (P, P)
+    where P: Val + Ord
+{
+    type Point = P;
+
+    fn start(&self) -> &P {
+        &self.0
+    }
+    fn end(&self) -> &P {
+        &self.1
+    }
+}
+
+/// Metadata for the maximum end point of the intervals in subtree, P is
+/// usually `T::Point` where `T: Interval`
+///
+/// As a search, selects the first interval ending after the point.
+#[derive(Clone, PartialEq)]
+pub struct IntervalMax<P>(P);
+
+impl<P> IntervalMax<P>
+    where P: Val + Ord
+{
+    /// Constructs a search for intervals ending after `point`
+    pub fn new(point: &P) -> Self {
+        IntervalMax(point.clone())
+    }
+
+    /// Returns the maximum end point
+    pub fn inner(&self) -> &P {
+        &self.0
+    }
+}
+
+impl<T> Meta<T> for IntervalMax<T::Point>
+    where T: Val + Interval
+{
+    fn from_t(t: &T) -> Self {
+        IntervalMax(t.end().clone())
+    }
+
+    fn merge(&mut self, other: &Self, _p: PhantomData<T>) {
+        if self.0 < other.0 {
+            self.0 = other.0.clone()
+        }
+    }
+}
+
+impl<T> Select<T> for IntervalMax<T::Point>
+    where T: Val + Interval
+{
+    fn select(&mut self, other: Cow<Self>) -> Selection {
+        if other.0 > self.0 {
+            Selection::Hit
+        } else {
+            Selection::Miss
+        }
+    }
+}
@@ -8,7 +8,7 @@ use std::borrow::Cow;
 use Val;
 use meta::{Meta, SubMeta, Select, Selection};
 
-use meta::checksum::CheckSum;
+use meta::checksum::{self, CheckSum};
 
 /// This `T` can be viewed as a Key-Value pair.
 pub trait Keyed {
@@ -84,9 +84,7 @@ impl<T> Meta<T> for KeySum<u64>
         KeySum(hasher.finish())
     }
     fn merge(&mut self, other: &Self, _t: PhantomData<T>) {
-        let a = self.0;
-        let b = other.0;
-        self.0 = (a ^ b).wrapping_add(a);
+        self.0 = checksum::combine(self.0, other.0);
     }
 }
 
@@ -101,9 +99,7 @@ impl<T> Meta<T> for ValSum<u64>
     }
 
     fn merge(&mut self, other: &Self, _t: PhantomData<T>) {
-        let a = self.0;
-        let b = other.0;
-        self.0 = (a ^ b).wrapping_add(a);
+        self.0 = checksum::combine(self.0, other.0);
     }
 }
 
@@ -114,7 +110,7 @@ impl<M> SubMeta<CheckSum<u64>> for M
         let k: Cow<KeySum<u64>> = self.submeta();
         let v: Cow<ValSum<u64>> = self.submeta();
 
-        let check = CheckSum::new(k.inner() ^ v.inner());
+        let check = CheckSum::new(checksum::combine(*k.inner(), *v.inner()));
         Cow::Owned(check)
     }
 }
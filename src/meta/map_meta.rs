@@ -0,0 +1,64 @@
+use std::marker::PhantomData;
+
+use Val;
+use meta::Meta;
+
+/// A one-off aggregation over elements, to be used as metadata through
+/// `MapMeta`.
+///
+/// `merge` has to be associative, since subtrees are merged in order but
+/// grouped in any way.
+pub trait Aggregate {
+    /// The element type aggregated over
+    type T: Val;
+    /// The aggregated value
+    type Acc: Clone;
+
+    /// Project an element into the aggregated value
+    fn project(t: &Self::T) -> Self::Acc;
+    /// Merge `b`, from the elements after `a`, into `a`
+    fn merge(a: &mut Self::Acc, b: &Self::Acc);
+}
+
+/// Metadata computed with the `Aggregate` `F`
+pub struct MapMeta<F>
+    where F: Aggregate
+{
+    acc: F::Acc,
+    _f: PhantomData<F>,
+}
+
+impl<F> MapMeta<F>
+    where F: Aggregate
+{
+    /// Returns the aggregated value
+    pub fn inner(&self) -> &F::Acc {
+        &self.acc
+    }
+}
+
+impl<F> Clone for MapMeta<F>
+    where F: Aggregate
+{
+    fn clone(&self) -> Self {
+        MapMeta {
+            acc: self.acc.clone(),
+            _f: PhantomData,
+        }
+    }
+}
+
+impl<F> Meta<F::T> for MapMeta<F>
+    where F: Aggregate
+{
+    fn from_t(t: &F::T) -> Self {
+        MapMeta {
+            acc: F::project(t),
+            _f: PhantomData,
+        }
+    }
+
+    fn merge(&mut self, other: &Self, _p: PhantomData<F::T>) {
+        F::merge(&mut self.acc, &other.acc)
+    }
+}
@@ -4,7 +4,7 @@ use std::borrow::Cow;
 use meta::{Meta, Select, Selection};
 
 /// Metadata for the maximum `T` in subtree.
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct Max<T>(T);
 
 impl<T> Meta<T> for Max<T>
@@ -0,0 +1,39 @@
+use Val;
+use std::marker::PhantomData;
+use std::borrow::Cow;
+use meta::{Meta, Select, Selection};
+
+/// Metadata for the minimum `T` in subtree.
+///
+/// Searches with `Min` go from the end of the collection, finding the
+/// last element less than or equal to the one searched for.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Min<T>(T);
+
+impl<T> Meta<T> for Min<T>
+    where T: Val + Ord + PartialEq
+{
+    fn from_t(t: &T) -> Self {
+        Min(t.clone())
+    }
+
+    fn merge(&mut self, other: &Self, _t: PhantomData<T>) {
+        if self.0 > other.0 {
+            self.0 = other.0.clone()
+        }
+    }
+}
+
+impl<T> Select<T> for Min<T>
+    where T: Val + Ord + PartialEq
+{
+    fn select(&mut self, other: Cow<Self>) -> Selection {
+        if self.0 == other.0 {
+            Selection::Hit
+        } else if self.0 > other.0 {
+            Selection::Between
+        } else {
+            Selection::Miss
+        }
+    }
+}
@@ -1,7 +1,21 @@
+pub mod bloom;
+pub mod bounds;
 pub mod cardinality;
 pub mod max;
+pub mod min;
 pub mod key;
+pub mod map_meta;
 pub mod checksum;
+pub mod digest;
+pub mod distinct;
+pub mod histogram;
+pub mod interval;
+pub mod multiplicity;
+pub mod prefix;
+pub mod sum;
+pub mod top_k;
+pub mod value_max;
+pub mod version;
 
 use std::marker::PhantomData;
 use std::borrow::Cow;
@@ -9,10 +23,24 @@ use std::borrow::Cow;
 use Val;
 use stash::Location;
 
+pub use meta::bloom::Bloom;
+pub use meta::bounds::Bounds;
 pub use meta::cardinality::Cardinality;
-pub use meta::checksum::CheckSum;
+pub use meta::checksum::{CheckSum, CheckSumWith};
+pub use meta::digest::Digest;
+pub use meta::distinct::Distinct;
+pub use meta::histogram::{Histogram, Bucketed, Buckets};
+pub use meta::interval::{Interval, IntervalMax};
 pub use meta::max::Max;
+pub use meta::min::Min;
 pub use meta::key::Key;
+pub use meta::map_meta::{Aggregate, MapMeta};
+pub use meta::multiplicity::Multiplicity;
+pub use meta::prefix::Prefix;
+pub use meta::sum::{Sum, Summed, Mean, ToF64};
+pub use meta::top_k::{TopK, Capacity};
+pub use meta::value_max::ValueMax;
+pub use meta::version::{Version, Versioned};
 
 /// Metadata for `T`
 pub trait Meta<T>
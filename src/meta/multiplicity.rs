@@ -0,0 +1,29 @@
+use std::marker::PhantomData;
+
+use Val;
+use meta::Meta;
+use meta::key::Keyed;
+
+/// The number of occurrences of elements in a subtree, where each
+/// element carries its own count as its value.
+#[derive(Clone, PartialEq)]
+pub struct Multiplicity<T>(T);
+
+impl<T> Multiplicity<T> {
+    /// Returns the count
+    pub fn inner(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Meta<T> for Multiplicity<usize>
+    where T: Val + Keyed<Value = usize>
+{
+    fn from_t(t: &T) -> Self {
+        Multiplicity(*t.value())
+    }
+
+    fn merge(&mut self, other: &Self, _p: PhantomData<T>) {
+        self.0 += other.0;
+    }
+}
@@ -0,0 +1,77 @@
+use std::marker::PhantomData;
+use std::borrow::Cow;
+
+use Val;
+use meta::{Meta, Select, Selection};
+use meta::key::Keyed;
+
+/// The longest common prefix of the keys in a subtree, for keys that
+/// can be viewed as slices of `B`, such as `u8` for strings.
+///
+/// Keys are assumed to be ordered like their slices.
+#[derive(Clone, PartialEq)]
+pub struct Prefix<B> {
+    prefix: Vec<B>,
+    // the last key in the subtree, to know when to skip past it
+    last: Vec<B>,
+}
+
+impl<B> Prefix<B>
+    where B: Clone
+{
+    /// Construct a new Prefix to search for
+    pub fn new(prefix: &[B]) -> Self {
+        Prefix {
+            prefix: prefix.to_vec(),
+            last: prefix.to_vec(),
+        }
+    }
+
+    /// Does `key` start with this prefix?
+    pub fn matches(&self, key: &[B]) -> bool
+        where B: PartialEq
+    {
+        key.starts_with(&self.prefix)
+    }
+}
+
+impl<T, B> Meta<T> for Prefix<B>
+    where T: Val + Keyed,
+          T::Key: AsRef<[B]>,
+          B: Clone + PartialEq
+{
+    fn from_t(t: &T) -> Self {
+        let key = t.key().as_ref().to_vec();
+        Prefix {
+            prefix: key.clone(),
+            last: key,
+        }
+    }
+
+    fn merge(&mut self, other: &Self, _t: PhantomData<T>) {
+        let common = self.prefix
+            .iter()
+            .zip(other.prefix.iter())
+            .take_while(|&(a, b)| a == b)
+            .count();
+        self.prefix.truncate(common);
+        self.last = other.last.clone();
+    }
+}
+
+impl<T, B> Select<T> for Prefix<B>
+    where T: Val + Keyed,
+          T::Key: AsRef<[B]>,
+          B: Clone + Ord
+{
+    fn select(&mut self, other: Cow<Self>) -> Selection {
+        if other.prefix.starts_with(&self.prefix) {
+            // every key in the subtree starts with the prefix
+            Selection::Hit
+        } else if other.last < self.prefix {
+            Selection::Miss
+        } else {
+            Selection::Between
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::marker::PhantomData;
+use std::ops::Add;
+
+use Val;
+use meta::Meta;
+
+/// This `T` contributes a number to a `Sum`.
+pub trait Summed {
+    /// The type of number summed
+    type Summand: Clone + Add<Output = Self::Summand>;
+
+    /// The number this element contributes to the sum
+    fn summand(&self) -> Self::Summand;
+}
+
+/// A number that can be converted to `f64`, possibly losing precision
+pub trait ToF64 {
+    /// Converts the number to `f64`
+    fn to_f64(&self) -> f64;
+}
+
+macro_rules! summed {
+    ($($num:ty),*) => (
+        $(
+            impl Summed for $num {
+                type Summand = $num;
+
+                fn summand(&self) -> $num {
+                    *self
+                }
+            }
+
+            impl ToF64 for $num {
+                fn to_f64(&self) -> f64 {
+                    *self as f64
+                }
+            }
+        )*
+    )
+}
+
+summed!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+/// The sum of all elements in a subtree, `N` is usually `T::Summand`
+/// where `T: Summed`
+#[derive(Clone, PartialEq)]
+pub struct Sum<N>(N);
+
+impl<N> Sum<N> {
+    /// Returns the sum
+    pub fn inner(&self) -> &N {
+        &self.0
+    }
+}
+
+impl<T> Meta<T> for Sum<T::Summand>
+    where T: Val + Summed
+{
+    fn from_t(t: &T) -> Self {
+        Sum(t.summand())
+    }
+
+    fn merge(&mut self, other: &Self, _p: PhantomData<T>) {
+        self.0 = self.0.clone() + other.0.clone();
+    }
+}
+
+/// The sum and count of all elements in a subtree, giving their mean.
+/// `N` is usually `T::Summand` where `T: Summed`
+#[derive(Clone, PartialEq)]
+pub struct Mean<N> {
+    sum: N,
+    count: usize,
+}
+
+impl<N> Mean<N> {
+    /// Returns the sum
+    pub fn sum(&self) -> &N {
+        &self.sum
+    }
+
+    /// Returns the number of elements summed
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the mean
+    pub fn mean(&self) -> f64
+        where N: ToF64
+    {
+        self.sum.to_f64() / self.count as f64
+    }
+}
+
+impl<T> Meta<T> for Mean<T::Summand>
+    where T: Val + Summed
+{
+    fn from_t(t: &T) -> Self {
+        Mean {
+            sum: t.summand(),
+            count: 1,
+        }
+    }
+
+    fn merge(&mut self, other: &Self, _p: PhantomData<T>) {
+        self.sum = self.sum.clone() + other.sum.clone();
+        self.count += other.count;
+    }
+}
@@ -0,0 +1,77 @@
+use std::marker::PhantomData;
+
+use Val;
+use meta::Meta;
+
+/// How many elements a `TopK` keeps, implemented for arrays `[T; K]`.
+pub trait Capacity {
+    /// The type of the elements kept
+    type T;
+    /// The number of elements kept
+    const K: usize;
+}
+
+impl<T, const K: usize> Capacity for [T; K] {
+    type T = T;
+    const K: usize = K;
+}
+
+/// Metadata for the K largest elements in subtree, A is `[T; K]`
+pub struct TopK<A>
+    where A: Capacity
+{
+    top: Vec<A::T>,
+}
+
+impl<A> TopK<A>
+    where A: Capacity
+{
+    /// Returns the largest elements, in descending order
+    pub fn inner(&self) -> &[A::T] {
+        &self.top
+    }
+}
+
+impl<A> Clone for TopK<A>
+    where A: Capacity,
+          A::T: Clone
+{
+    fn clone(&self) -> Self {
+        TopK { top: self.top.clone() }
+    }
+}
+
+impl<T, A> Meta<T> for TopK<A>
+    where T: Val + Ord,
+          A: Capacity<T = T>
+{
+    fn from_t(t: &T) -> Self {
+        TopK { top: vec![t.clone()] }
+    }
+
+    fn merge(&mut self, other: &Self, _t: PhantomData<T>) {
+        let mut merged = Vec::with_capacity(A::K);
+        {
+            let mut a = self.top.iter().peekable();
+            let mut b = other.top.iter().peekable();
+            while merged.len() < A::K {
+                let next = match (a.peek(), b.peek()) {
+                    (Some(x), Some(y)) => {
+                        if x >= y {
+                            a.next()
+                        } else {
+                            b.next()
+                        }
+                    }
+                    (Some(_), None) => a.next(),
+                    (None, _) => b.next(),
+                };
+                match next {
+                    Some(t) => merged.push(t.clone()),
+                    None => break,
+                }
+            }
+        }
+        self.top = merged;
+    }
+}
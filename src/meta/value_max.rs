@@ -0,0 +1,31 @@
+use std::marker::PhantomData;
+
+use Val;
+use meta::Meta;
+use meta::key::Keyed;
+
+/// Metadata for the maximum value of the key-value pairs in subtree.
+#[derive(Clone, PartialEq)]
+pub struct ValueMax<V>(V);
+
+impl<V> ValueMax<V> {
+    /// Returns the maximum value
+    pub fn inner(&self) -> &V {
+        &self.0
+    }
+}
+
+impl<T, V> Meta<T> for ValueMax<V>
+    where T: Val + Keyed<Value = V>,
+          V: Clone + Ord
+{
+    fn from_t(t: &T) -> Self {
+        ValueMax(t.value().clone())
+    }
+
+    fn merge(&mut self, other: &Self, _p: PhantomData<T>) {
+        if self.0 < other.0 {
+            self.0 = other.0.clone()
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::marker::PhantomData;
+
+use Val;
+use meta::Meta;
+
+/// This `T` carries a logical version, such as the time it was last
+/// modified.
+pub trait Versioned {
+    /// The version of the element
+    fn version(&self) -> u64;
+}
+
+/// Metadata for the maximum version of the elements in subtree.
+#[derive(Clone, PartialEq)]
+pub struct Version<T>(T);
+
+impl<T> Version<T> {
+    /// Returns the maximum version
+    pub fn inner(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Meta<T> for Version<u64>
+    where T: Val + Versioned
+{
+    fn from_t(t: &T) -> Self {
+        Version(t.version())
+    }
+
+    fn merge(&mut self, other: &Self, _p: PhantomData<T>) {
+        if self.0 < other.0 {
+            self.0 = other.0
+        }
+    }
+}
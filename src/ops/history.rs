@@ -0,0 +1,153 @@
+use Val;
+
+use collection::Collection;
+
+use meta::Meta;
+
+/// A collection along with all its earlier versions.
+///
+/// Every version shares the unchanged parts of its tree with the others,
+/// so keeping many versions is cheap.
+pub struct History<T, M>
+    where T: Val,
+          M: Meta<T>
+{
+    // Oldest first, the last one is the current version
+    versions: Vec<Collection<T, M>>,
+}
+
+impl<T, M> History<T, M>
+    where T: Val,
+          M: Meta<T>
+{
+    /// Constructs a history with `collection` as its first version
+    pub fn new(collection: Collection<T, M>) -> Self {
+        History { versions: vec![collection] }
+    }
+
+    /// Returns the current version
+    pub fn current(&self) -> &Collection<T, M> {
+        self.versions.last().expect("there is always a version")
+    }
+
+    /// Applies `f` to a copy of the current version, and records the
+    /// result as the new current version
+    pub fn update<F, R>(&mut self, f: F) -> R
+        where F: FnOnce(&mut Collection<T, M>) -> R
+    {
+        let mut next = self.last_mut().clone_mut();
+        let result = f(&mut next);
+        self.versions.push(next);
+        result
+    }
+
+    /// Returns the number of versions recorded, at least one
+    pub fn version_count(&self) -> usize {
+        self.versions.len()
+    }
+
+    /// Returns version `n`, counting from the first one
+    pub fn version(&self, n: usize) -> Option<&Collection<T, M>> {
+        self.versions.get(n)
+    }
+
+    /// Returns all versions, oldest first
+    pub fn versions(&self) -> &[Collection<T, M>] {
+        &self.versions
+    }
+
+    /// Records a copy of version `n` as the new current version, leaving
+    /// the versions after it in the history.
+    ///
+    /// Returns `false` if there is no version `n`.
+    pub fn checkout(&mut self, n: usize) -> bool {
+        if n >= self.versions.len() {
+            return false;
+        }
+        let version = self.versions[n].clone_mut();
+        self.versions.push(version);
+        true
+    }
+
+    /// Drops the current version, making the one before it current.
+    ///
+    /// Returns the dropped version, or `None` if it is the only one.
+    pub fn undo(&mut self) -> Option<Collection<T, M>> {
+        if self.versions.len() > 1 {
+            self.versions.pop()
+        } else {
+            None
+        }
+    }
+
+    fn last_mut(&mut self) -> &mut Collection<T, M> {
+        self.versions.last_mut().expect("there is always a version")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    const LOTS: usize = 100_000;
+
+    use std::hash::Hash;
+
+    use meta::cardinality::Cardinality;
+    use meta::checksum::CheckSum;
+
+    use collection::Collection;
+
+    use ops::vector::VectorOps;
+
+    use super::History;
+
+    collection!(Vector<T> {
+        cardinality: Cardinality<usize>,
+        checksum: CheckSum<u64>,
+    } where T: Hash);
+
+    #[test]
+    fn history() {
+        let mut vec = Vector::new();
+        for i in 0..LOTS {
+            vec.push(i);
+        }
+
+        let mut history = History::new(vec);
+
+        for i in 0..100 {
+            history.update(|vec| vec.push(LOTS + i));
+        }
+        assert_eq!(history.version_count(), 101);
+
+        for n in 0..101 {
+            let version = history.version(n).unwrap();
+            assert_eq!(version.len(), LOTS + n);
+            assert_eq!(version.get(LOTS - 1), Some(&(LOTS - 1)));
+        }
+        assert!(history.version(101).is_none());
+        assert_eq!(history.versions().len(), 101);
+
+        let removed = history.update(|vec| vec.remove(0));
+        assert_eq!(removed, Some(0));
+        assert_eq!(history.current().len(), LOTS + 99);
+        assert_eq!(history.current().get(0), Some(&1));
+
+        let undone = history.undo().unwrap();
+        assert_eq!(undone.len(), LOTS + 99);
+        assert_eq!(history.current().len(), LOTS + 100);
+        assert_eq!(history.current().get(0), Some(&0));
+
+        assert!(history.checkout(0));
+        assert!(!history.checkout(1000));
+        assert_eq!(history.version_count(), 102);
+        assert!(*history.current() == *history.version(0).unwrap());
+
+        history.update(|vec| vec.push(0));
+        assert_eq!(history.current().len(), LOTS + 1);
+        assert_eq!(history.version(0).unwrap().len(), LOTS);
+
+        while history.undo().is_some() {}
+        assert_eq!(history.version_count(), 1);
+        assert_eq!(history.current().len(), LOTS);
+    }
+}
@@ -0,0 +1,158 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use Val;
+
+use collection::Collection;
+
+use meta::{Meta, SubMeta};
+use meta::interval::{Interval, IntervalMax};
+
+use stash::Location;
+
+use tree::node::Child;
+
+/// Operations on collections of intervals, ordered by start point, with
+/// `IntervalMax` metadata
+pub trait IntervalOps<T>
+    where T: Interval
+{
+    /// Returns the intervals that overlap with `range`, in order
+    fn find_overlapping(&self, range: Range<T::Point>) -> Vec<&T>;
+    /// Returns the intervals containing `point`, in order
+    fn stab(&self, point: &T::Point) -> Vec<&T>;
+}
+
+impl<T, M> IntervalOps<T> for Collection<T, M>
+    where T: Val + Interval,
+          M: Meta<T> + SubMeta<IntervalMax<T::Point>>
+{
+    fn find_overlapping(&self, range: Range<T::Point>) -> Vec<&T> {
+        let mut found = vec![];
+        overlapping(self, self.root, &range, &mut found);
+        found
+    }
+
+    fn stab(&self, point: &T::Point) -> Vec<&T> {
+        let mut found = vec![];
+        for t in self.iter_from_using(IntervalMax::new(point)) {
+            if t.start() > point {
+                break;
+            }
+            if t.end() > point {
+                found.push(t);
+            }
+        }
+        found
+    }
+}
+
+// Collects the intervals below `location` overlapping with `range` into
+// `found`, skipping subtrees ending before it. Returns false once past
+// the end of the range.
+fn overlapping<'a, T, M>(collection: &'a Collection<T, M>,
+                         location: Location<T, M>,
+                         range: &Range<T::Point>,
+                         found: &mut Vec<&'a T>)
+                         -> bool
+    where T: Val + Interval,
+          M: Meta<T> + SubMeta<IntervalMax<T::Point>>
+{
+    let node = collection.stash.get(location);
+    for child in &node.children {
+        match *child {
+            Child::Node { location: child_location, ref meta } => {
+                let max: Cow<IntervalMax<T::Point>> = meta.submeta();
+                if *max.inner() > range.start &&
+                   !overlapping(collection,
+                                child_location.relative(location.depth),
+                                range,
+                                found) {
+                    return false;
+                }
+            }
+            Child::Leaf(ref t) => {
+                if *t.start() >= range.end {
+                    return false;
+                }
+                if *t.end() > range.start {
+                    found.push(t);
+                }
+            }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    const LOTS: usize = 100_000;
+
+    use std::hash::Hash;
+
+    use meta::max::Max;
+    use meta::interval::{Interval, IntervalMax};
+
+    use collection::Collection;
+
+    use ops::set::SetOps;
+
+    use super::IntervalOps;
+
+    collection!(Intervals<T> {
+        max: Max<T>,
+        interval: IntervalMax<T::Point>,
+    } where T: Ord + Hash + Interval);
+
+    // Intervals of varying length, a few of them long
+    fn interval(i: usize) -> (usize, usize) {
+        let len = if i.is_multiple_of(1000) { 5000 } else { i % 7 + 1 };
+        (i * 3, i * 3 + len)
+    }
+
+    #[test]
+    fn find_overlapping() {
+        let mut set = Intervals::new();
+
+        for i in 0..LOTS {
+            set.insert(interval(i));
+        }
+
+        for i in 0..100 {
+            let start = i * LOTS * 3 / 100;
+            let end = start + i * 11;
+            let expected: Vec<_> = (0..LOTS)
+                .map(interval)
+                .filter(|&(s, e)| s < end && e > start)
+                .collect();
+            let found: Vec<_> = set.find_overlapping(start..end)
+                .into_iter()
+                .cloned()
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn stab() {
+        let mut set = Intervals::new();
+
+        for i in 0..LOTS {
+            set.insert(interval(i));
+        }
+
+        for i in 0..100 {
+            let point = i * LOTS * 3 / 100 + i;
+            let expected: Vec<_> = (0..LOTS)
+                .map(interval)
+                .filter(|&(s, e)| s <= point && e > point)
+                .collect();
+            let found: Vec<_> = set.stab(&point)
+                .into_iter()
+                .cloned()
+                .collect();
+            assert_eq!(found, expected);
+        }
+        assert!(set.stab(&(LOTS * 10)).is_empty());
+    }
+}
@@ -1,16 +1,28 @@
 use std::hash::Hash;
-use std::cmp::Ord;
-use std::ops::{Deref, DerefMut};
+use std::cmp::{Ord, Ordering};
+use std::ops::{Deref, DerefMut, Range};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::iter::{FromIterator, Peekable};
 
 use Val;
 
 use collection::{Collection, MutContext};
 
-use meta::{Meta, SubMeta};
+use meta::{Meta, SubMeta, Select, Selection};
+use meta::bloom::Bloom;
 use meta::key::{Key, KeySum, Keyed};
+use meta::cardinality::Cardinality;
+use meta::checksum::CheckSum;
+use meta::prefix::Prefix;
+use meta::value_max::ValueMax;
+
+use stash::{Stash, Location};
 
 use tree::branch::{Branch, BranchResult};
+use tree::node::Child;
 use tree::level::{Beginning, End, Relative};
+use tree::iterator::Iter;
 use tree::weight::Weight;
 
 /// A Key-Value pair
@@ -33,8 +45,17 @@ impl<K, V> KV<K, V>
     fn val(&self) -> &V {
         &self.v
     }
-    fn into_val(self) -> V {
-        self.v
+    fn into_pair(self) -> (K, V) {
+        (self.k, self.v)
+    }
+}
+
+impl<K, V> From<(K, V)> for KV<K, V>
+    where K: Val + Ord + PartialEq,
+          V: Clone
+{
+    fn from((k, v): (K, V)) -> Self {
+        KV::new(k, v)
     }
 }
 
@@ -110,16 +131,94 @@ pub trait MapOps<K, V, M>
           K: Val + Ord,
           V: Clone
 {
+    /// Build a map from key-value pairs, already sorted by key, without
+    /// searching the tree for every pair
+    fn from_sorted_pairs<I>(pairs: I) -> Self
+        where I: IntoIterator<Item = (K, V)>;
     /// Insert a value `V` at key `K`
     fn insert(&mut self, key: K, V);
+    /// Insert all `(key, value)` pairs of `iter`, in order
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I);
     /// Remove value at key `K`
     fn remove(&mut self, key: K) -> Option<V>;
+    /// Remove value at key `K`, returning it along with the stored key
+    fn remove_entry(&mut self, key: K) -> Option<(K, V)>;
     /// Get a reference to the value at key `K`
     fn get(&self, key: K) -> Option<&V>;
+    /// Get references to the stored key equal to `K`, and its value
+    fn get_key_value(&self, key: K) -> Option<(&K, &V)>;
+    /// Is there a value at key `K`?
+    fn contains_key(&self, key: K) -> bool;
+    /// Get a reference to the value at key `K`, first inserting `f()`
+    /// if there is none
+    fn get_or_insert_with<F>(&mut self, key: K, f: F) -> &V
+        where F: FnOnce() -> V;
     /// Get a mutable reference to the value at key `K`
     fn get_mut(&mut self,
                key: K)
                -> Option<ValContext<KV<K, V>, M, Beginning>>;
+    /// Get references to the first key and value in the map
+    fn first_key_value(&self) -> Option<(&K, &V)>;
+    /// Get references to the last key and value in the map
+    fn last_key_value(&self) -> Option<(&K, &V)>;
+    /// Remove and return the first key and value in the map
+    fn pop_first(&mut self) -> Option<(K, V)>;
+    /// Remove and return the last key and value in the map
+    fn pop_last(&mut self) -> Option<(K, V)>;
+    /// Build a new map with the same keys, and values transformed by `f`
+    fn map_values<W, N, F>(&self, f: F) -> Collection<KV<K, W>, N>
+        where W: Clone,
+              N: Meta<KV<K, W>>,
+              F: Fn(&V) -> W;
+    /// Keep only the entries for which `f(key, value)` returns `true`
+    fn retain<F>(&mut self, f: F) where F: FnMut(&K, &V) -> bool;
+}
+
+impl<K, V, M> FromIterator<(K, V)> for Collection<KV<K, V>, M>
+    where M: Meta<KV<K, V>> + SubMeta<Key<K>>,
+          K: Val + Ord,
+          V: Clone
+{
+    /// Builds a map from `(key, value)` pairs in any order. Like with
+    /// `insert`, later values overwrite earlier ones with the same key.
+    fn from_iter<I>(iter: I) -> Self
+        where I: IntoIterator<Item = (K, V)>
+    {
+        let mut pairs: Vec<(K, V)> = iter.into_iter().collect();
+        // Stable, so pairs with equal keys stay in insertion order
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut deduped: Vec<(K, V)> = Vec::with_capacity(pairs.len());
+        for (k, v) in pairs {
+            let overwrite = deduped.last().map(|l| l.0 == k).unwrap_or(false);
+            if overwrite {
+                deduped.pop();
+            }
+            deduped.push((k, v));
+        }
+        Collection::from_sorted_pairs(deduped)
+    }
+}
+
+impl<'a, K, V, M> From<&'a Collection<KV<K, V>, M>> for BTreeMap<K, V>
+    where M: Meta<KV<K, V>>,
+          K: Val + Ord,
+          V: Clone
+{
+    fn from(collection: &'a Collection<KV<K, V>, M>) -> Self {
+        collection.iter_pairs().map(|(k, v)| (k.clone(), v.clone())).collect()
+    }
+}
+
+impl<K, V, M> From<BTreeMap<K, V>> for Collection<KV<K, V>, M>
+    where M: Meta<KV<K, V>> + SubMeta<Key<K>>,
+          K: Val + Ord,
+          V: Clone
+{
+    /// Builds a map of the entries of `map`, which are already in key order
+    fn from(map: BTreeMap<K, V>) -> Self {
+        Collection::from_sorted_pairs(map)
+    }
 }
 
 /// Operations on a map with `KeySum` metadata
@@ -131,6 +230,104 @@ pub trait MapOpsKeySum<K, V, M>
 {
     /// Merge two maps, overwriting values from `self` with `b`
     fn merge(&mut self, b: &mut Self) -> Self;
+    /// Merge two maps, combining the values of keys present in both
+    /// with `f(self_value, b_value)`
+    fn merge_with<F>(&mut self, b: &mut Self, f: F) -> Self
+        where F: Fn(&V, &V) -> V;
+    /// Returns the entries of `self` with keys that are also in `b`
+    fn intersect_keys(&mut self, b: &mut Self) -> Self;
+    /// Returns the entries of `self` with keys that are not in `b`
+    fn difference_keys(&mut self, b: &mut Self) -> Self;
+}
+
+/// A single difference between two maps
+#[derive(Clone, Debug, PartialEq)]
+pub enum MapChange<K, V> {
+    /// The key is only present in the new map
+    Added(K, V),
+    /// The key is only present in the old map
+    Removed(K, V),
+    /// The key is present in both maps with different values, old and new
+    Modified(K, V, V),
+}
+
+/// Operations on a map with `CheckSum` metadata
+pub trait MapOpsCheckSum<K, V, M>
+    where Self: MapOps<K, V, M>,
+          M: Meta<KV<K, V>>,
+          K: Val + Ord,
+          V: Clone
+{
+    /// Returns the changes that turn `self` into `other`, in key order.
+    ///
+    /// Subtrees with equal checksums are skipped, so the work done is
+    /// proportional to the size of the change rather than of the maps.
+    fn diff(&self, other: &Self) -> Vec<MapChange<K, V>>;
+    /// Merges the changes made in `a` and `b` since their common ancestor
+    /// `base` into a new map.
+    ///
+    /// Keys changed on one side only take the value of that side. Keys
+    /// changed differently on both sides are conflicts, passed to
+    /// `resolve` with the value in `base`, `a` and `b`, where `None` means
+    /// that the key is absent. The key is removed if `resolve` returns
+    /// `None`.
+    fn merge3<F>(base: &Self, a: &mut Self, b: &Self, resolve: F) -> Self
+        where V: PartialEq,
+              F: FnMut(&K, Option<&V>, Option<&V>, Option<&V>) -> Option<V>;
+}
+
+/// Operations on a map with `Prefix` metadata
+pub trait MapOpsPrefix<K, V, M>
+    where Self: MapOps<K, V, M>,
+          M: Meta<KV<K, V>>,
+          K: Val + Ord,
+          V: Clone
+{
+    /// Returns an iterator over the `(key, value)` pairs with keys
+    /// starting with `prefix`, in key order
+    fn iter_prefix<'a>(&'a self, prefix: &[u8]) -> PrefixPairs<'a, K, V, M>;
+}
+
+/// Operations on a map with `Bloom` metadata
+pub trait MapOpsBloom<K, V, M>
+    where Self: MapOps<K, V, M>,
+          M: Meta<KV<K, V>>,
+          K: Val + Ord,
+          V: Clone
+{
+    /// Does the map contain key `K`?
+    ///
+    /// Like `contains_key`, but stops as soon as the bloom filter of a
+    /// subtree rules the key out, without visiting the nodes below.
+    fn contains_key_bloom(&self, key: K) -> bool;
+}
+
+/// Operations on a map with `ValueMax` metadata
+pub trait MapOpsValueMax<K, V, M>
+    where Self: MapOps<K, V, M>,
+          M: Meta<KV<K, V>>,
+          K: Val + Ord,
+          V: Clone + Ord
+{
+    /// Returns the pair holding the maximum value, the first by key if
+    /// there are several
+    fn max_by_value(&self) -> Option<(&K, &V)>;
+    /// Returns the pairs with values `v` where `range.start <= v <
+    /// range.end`, in key order
+    ///
+    /// Subtrees with no value reaching `range.start` are skipped.
+    fn find_by_value(&self, range: Range<V>) -> Vec<(&K, &V)>;
+}
+
+/// Operations on a map with `Cardinality` metadata
+pub trait MapOpsCardinality<K, V, M>
+    where Self: MapOps<K, V, M>,
+          M: Meta<KV<K, V>>,
+          K: Val + Ord,
+          V: Clone
+{
+    /// Count the keys `k` in the map where `from <= k < to`
+    fn count_range(&self, from: K, to: K) -> usize;
 }
 
 impl<K, V, M> MapOps<K, V, M> for Collection<KV<K, V>, M>
@@ -138,6 +335,12 @@ impl<K, V, M> MapOps<K, V, M> for Collection<KV<K, V>, M>
           K: Val + Ord,
           V: Clone
 {
+    fn from_sorted_pairs<I>(pairs: I) -> Self
+        where I: IntoIterator<Item = (K, V)>
+    {
+        Collection::from_sorted(pairs.into_iter().map(|(k, v)| KV::new(k, v)))
+    }
+
     fn insert(&mut self, key: K, val: V) {
         let mut search = Key::new(key.clone());
         let branch = Branch::<_, _, Beginning>::new_full(self.root,
@@ -163,7 +366,17 @@ impl<K, V, M> MapOps<K, V, M> for Collection<KV<K, V>, M>
         }
     }
 
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+
     fn remove(&mut self, key: K) -> Option<V> {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    fn remove_entry(&mut self, key: K) -> Option<(K, V)> {
         let mut key = Key::new(key);
 
         let branch = Branch::<_, _, Beginning>::new_full(self.root,
@@ -175,7 +388,7 @@ impl<K, V, M> MapOps<K, V, M> for Collection<KV<K, V>, M>
             BranchResult::Hit(mut b) => {
                 let res = b.remove(self.divisor, &mut self.stash);
                 self.root = b.root();
-                res.map(|kv| kv.into_val())
+                res.map(|kv| kv.into_pair())
             }
         }
     }
@@ -193,6 +406,53 @@ impl<K, V, M> MapOps<K, V, M> for Collection<KV<K, V>, M>
         }
     }
 
+    fn get_key_value(&self, key: K) -> Option<(&K, &V)> {
+        let mut key = Key::new(key);
+        let res: BranchResult<_, _, Beginning> =
+            Branch::new_full(self.root, &mut key, &self.stash);
+
+        match res {
+            BranchResult::Hit(branch) => {
+                branch.leaf(&self.stash).map(|kv| (&kv.k, &kv.v))
+            }
+            _ => None,
+        }
+    }
+
+    fn contains_key(&self, key: K) -> bool {
+        let mut key = Key::new(key);
+        let res: BranchResult<_, _, Beginning> =
+            Branch::new_full(self.root, &mut key, &self.stash);
+
+        matches!(res, BranchResult::Hit(_))
+    }
+
+    fn get_or_insert_with<F>(&mut self, key: K, f: F) -> &V
+        where F: FnOnce() -> V
+    {
+        let mut search = Key::new(key.clone());
+        let branch = Branch::<_, _, Beginning>::new_full(self.root,
+                                                         &mut search,
+                                                         &self.stash);
+        match branch {
+            BranchResult::Hit(b) => {
+                b.leaf(&self.stash).map(|l| l.val()).expect("hit")
+            }
+            BranchResult::Between(mut b) => {
+                b.insert(KV::new(key, f()), self.divisor, &mut self.stash);
+                self.root = b.root();
+                b.leaf(&self.stash).map(|l| l.val()).expect("inserted")
+            }
+            BranchResult::Miss => {
+                let mut b: Branch<_, _, End> = Branch::first(self.root,
+                                                             &self.stash);
+                b.insert(KV::new(key, f()), self.divisor, &mut self.stash);
+                self.root = b.root();
+                b.leaf(&self.stash).map(|l| l.val()).expect("inserted")
+            }
+        }
+    }
+
     fn get_mut(&mut self,
                key: K)
                -> Option<ValContext<KV<K, V>, M, Beginning>> {
@@ -206,110 +466,1410 @@ impl<K, V, M> MapOps<K, V, M> for Collection<KV<K, V>, M>
             None
         }
     }
+
+    fn first_key_value(&self) -> Option<(&K, &V)> {
+        let branch: Branch<_, _, Beginning> = Branch::first(self.root,
+                                                            &self.stash);
+        branch.leaf(&self.stash).map(|kv| (&kv.k, &kv.v))
+    }
+
+    fn last_key_value(&self) -> Option<(&K, &V)> {
+        let branch: Branch<_, _, End> = Branch::first(self.root, &self.stash);
+        branch.leaf(&self.stash).map(|kv| (&kv.k, &kv.v))
+    }
+
+    fn pop_first(&mut self) -> Option<(K, V)> {
+        let mut branch: Branch<_, _, Beginning> = Branch::first(self.root,
+                                                                &self.stash);
+        let ret = branch.remove(self.divisor, &mut self.stash);
+        self.root = branch.root();
+        ret.map(|kv| kv.into_pair())
+    }
+
+    fn pop_last(&mut self) -> Option<(K, V)> {
+        let mut branch: Branch<_, _, End> = Branch::first(self.root,
+                                                          &self.stash);
+        let ret = branch.remove(self.divisor, &mut self.stash);
+        self.root = branch.root();
+        ret.map(|kv| kv.into_pair())
+    }
+
+    fn map_values<W, N, F>(&self, f: F) -> Collection<KV<K, W>, N>
+        where W: Clone,
+              N: Meta<KV<K, W>>,
+              F: Fn(&V) -> W
+    {
+        // Weights only depend on the keys, so the tree keeps its shape
+        Collection::from_sorted(self.iter()
+            .map(|kv| KV::new(kv.k.clone(), f(&kv.v))))
+    }
+
+    fn retain<F>(&mut self, mut f: F)
+        where F: FnMut(&K, &V) -> bool
+    {
+        // Removing path by path leaves all untouched subtrees shared
+        let remove: Vec<K> = self.iter()
+            .filter(|kv| !f(&kv.k, &kv.v))
+            .map(|kv| kv.k.clone())
+            .collect();
+
+        for key in remove {
+            self.remove(key);
+        }
+    }
 }
 
-impl<K, V, M> MapOpsKeySum<K, V, M> for Collection<KV<K, V>, M>
-    where M: Meta<KV<K, V>> + SubMeta<Key<K>> + SubMeta<KeySum<u64>>,
-          K: Val + Ord + Hash,
-          V: Clone
+/// An iterator over the keys of a map, in order
+pub struct Keys<'a, K, V, M>
+    where K: 'a + Val + Ord,
+          V: 'a + Clone,
+          M: 'a + Meta<KV<K, V>>
 {
-    fn merge(&mut self, b: &mut Self) -> Self {
-        self.union_using::<Key<K>, KeySum<u64>>(b)
+    inner: Iter<'a, KV<K, V>, M, Beginning>,
+}
+
+impl<'a, K, V, M> Iterator for Keys<'a, K, V, M>
+    where K: 'a + Val + Ord,
+          V: 'a + Clone,
+          M: 'a + Meta<KV<K, V>>
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|kv| &kv.k)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    extern crate rand;
+/// An iterator over the values of a map, in key order
+pub struct Values<'a, K, V, M>
+    where K: 'a + Val + Ord,
+          V: 'a + Clone,
+          M: 'a + Meta<KV<K, V>>
+{
+    inner: Iter<'a, KV<K, V>, M, Beginning>,
+}
 
-    const LOTS: usize = 100_000;
+impl<'a, K, V, M> Iterator for Values<'a, K, V, M>
+    where K: 'a + Val + Ord,
+          V: 'a + Clone,
+          M: 'a + Meta<KV<K, V>>
+{
+    type Item = &'a V;
 
-    use std::hash::Hash;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|kv| &kv.v)
+    }
+}
 
-    use meta::key::{Key, Keyed, KeySum, ValSum};
+/// An iterator over the key-value pairs of a map, in key order
+pub struct Pairs<'a, K, V, M>
+    where K: 'a + Val + Ord,
+          V: 'a + Clone,
+          M: 'a + Meta<KV<K, V>>
+{
+    inner: Iter<'a, KV<K, V>, M, Beginning>,
+}
 
-    use collection::Collection;
+impl<'a, K, V, M> Iterator for Pairs<'a, K, V, M>
+    where K: 'a + Val + Ord,
+          V: 'a + Clone,
+          M: 'a + Meta<KV<K, V>>
+{
+    type Item = (&'a K, &'a V);
 
-    use super::MapOps;
-    use super::MapOpsKeySum;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|kv| (&kv.k, &kv.v))
+    }
+}
 
-    collection!(Map<T> {
-        key: Key<T::Key>,
-        keysum: KeySum<u64>,
-        valsum: ValSum<u64>,
-    } where T: Keyed, T::Key: Hash, T::Value: Hash);
+/// An iterator over the keys of two maps, in order, along with the
+/// values for each key in either map
+pub struct ZipByKey<'a, K, V, M, W, N>
+    where K: 'a + Val + Ord,
+          V: 'a + Clone,
+          M: 'a + Meta<KV<K, V>>,
+          W: 'a + Clone,
+          N: 'a + Meta<KV<K, W>>
+{
+    a: Peekable<Iter<'a, KV<K, V>, M, Beginning>>,
+    b: Peekable<Iter<'a, KV<K, W>, N, Beginning>>,
+}
 
-    #[test]
-    fn insert() {
-        let mut map = Map::new();
-        map.insert("a", 1);
-        assert_eq!(map.get("a"), Some(&1));
+impl<'a, K, V, M, W, N> Iterator for ZipByKey<'a, K, V, M, W, N>
+    where K: 'a + Val + Ord,
+          V: 'a + Clone,
+          M: 'a + Meta<KV<K, V>>,
+          W: 'a + Clone,
+          N: 'a + Meta<KV<K, W>>
+{
+    type Item = (&'a K, Option<&'a V>, Option<&'a W>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let order = match (self.a.peek(), self.b.peek()) {
+            (Some(a), Some(b)) => a.k.cmp(&b.k),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => return None,
+        };
+        match order {
+            Ordering::Less => self.a.next().map(|a| (&a.k, Some(&a.v), None)),
+            Ordering::Greater => {
+                self.b.next().map(|b| (&b.k, None, Some(&b.v)))
+            }
+            Ordering::Equal => {
+                match (self.a.next(), self.b.next()) {
+                    (Some(a), Some(b)) => Some((&a.k, Some(&a.v), Some(&b.v))),
+                    _ => None,
+                }
+            }
+        }
     }
+}
 
-    #[test]
-    fn partial_eq() {
-        let mut a = Map::new();
-        let mut b = Map::new();
+/// An iterator over the key-value pairs of a map with keys starting
+/// with a prefix, in key order
+pub struct PrefixPairs<'a, K, V, M>
+    where K: 'a + Val + Ord,
+          V: 'a + Clone,
+          M: 'a + Meta<KV<K, V>>
+{
+    inner: Iter<'a, KV<K, V>, M, Beginning>,
+    prefix: Prefix<u8>,
+}
 
-        for i in 0..LOTS {
-            a.insert(i, i + 1);
-            b.insert(LOTS - i - 1, LOTS - i - 1);
+impl<'a, K, V, M> Iterator for PrefixPairs<'a, K, V, M>
+    where K: 'a + Val + Ord + AsRef<[u8]>,
+          V: 'a + Clone,
+          M: 'a + Meta<KV<K, V>>
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // keys with the prefix are all next to each other
+        match self.inner.next() {
+            Some(kv) if self.prefix.matches(kv.k.as_ref()) => {
+                Some((&kv.k, &kv.v))
+            }
+            _ => None,
         }
+    }
+}
 
-        // mutate in a
-        for i in 0..LOTS {
-            a.get_mut(i).map(|mut v| *v -= 1);
+impl<K, V, M> Collection<KV<K, V>, M>
+    where K: Val + Ord,
+          V: Clone,
+          M: Meta<KV<K, V>>
+{
+    /// Returns an iterator over the keys of both `self` and `other` in
+    /// order, along with the value in each map, if any
+    pub fn zip_by_key<'a, W, N>(&'a self,
+                                other: &'a Collection<KV<K, W>, N>)
+                                -> ZipByKey<'a, K, V, M, W, N>
+        where W: Clone,
+              N: Meta<KV<K, W>>
+    {
+        ZipByKey {
+            a: self.iter().peekable(),
+            b: other.iter().peekable(),
         }
+    }
 
-        assert!(a == b);
+    /// Returns an iterator over `(key, value)` pairs of the map
+    pub fn iter_pairs<'a>(&'a self) -> Pairs<'a, K, V, M> {
+        Pairs { inner: self.iter() }
     }
 
-    #[test]
-    fn overwrite() {
-        let mut map = Map::new();
+    /// Returns an iterator over the keys of the map
+    pub fn keys<'a>(&'a self) -> Keys<'a, K, V, M> {
+        Keys { inner: self.iter() }
+    }
 
-        map.insert("a", 1);
-        assert_eq!(map.get("a"), Some(&1));
-        map.insert("a", 2);
-        assert_eq!(map.get("a"), Some(&2));
+    /// Returns an iterator over the values of the map
+    pub fn values<'a>(&'a self) -> Values<'a, K, V, M> {
+        Values { inner: self.iter() }
     }
+}
 
-    #[test]
-    fn clone() {
-        let mut a = Map::new();
+impl<K, V, M> MapOpsKeySum<K, V, M> for Collection<KV<K, V>, M>
+    where M: Meta<KV<K, V>> + SubMeta<Key<K>> + SubMeta<KeySum<u64>>,
+          K: Val + Ord + Hash,
+          V: Clone
+{
+    fn merge(&mut self, b: &mut Self) -> Self {
+        self.union_using::<Key<K>, KeySum<u64>>(b)
+    }
 
-        a.insert("a", 1);
+    fn merge_with<F>(&mut self, b: &mut Self, f: F) -> Self
+        where F: Fn(&V, &V) -> V
+    {
+        let mut merged = self.merge(b);
 
-        let mut b = a.clone_mut();
+        let mut collisions = vec![];
+        {
+            let mut a_iter = self.iter().peekable();
+            let mut b_iter = b.iter().peekable();
+            while let (Some(a), Some(b)) = (a_iter.peek(), b_iter.peek()) {
+                match a.k.cmp(&b.k) {
+                    Ordering::Less => {
+                        a_iter.next();
+                    }
+                    Ordering::Greater => {
+                        b_iter.next();
+                    }
+                    Ordering::Equal => {
+                        if let (Some(a), Some(b)) = (a_iter.next(),
+                                                     b_iter.next()) {
+                            collisions.push((a.k.clone(), f(&a.v, &b.v)));
+                        }
+                    }
+                }
+            }
+        }
 
-        b.insert("a", 2);
+        for (k, v) in collisions {
+            merged.insert(k, v);
+        }
+        merged
+    }
 
-        assert_eq!(a.get("a"), Some(&1));
-        assert_eq!(b.get("a"), Some(&2));
+    fn intersect_keys(&mut self, b: &mut Self) -> Self {
+        self.intersection_using::<Key<K>, KeySum<u64>>(b)
     }
 
-    #[test]
-    fn merge() {
-        let mut a = Map::new();
-        let mut b = Map::new();
+    fn difference_keys(&mut self, b: &mut Self) -> Self {
+        self.difference_using::<Key<K>, KeySum<u64>>(b)
+    }
+}
 
-        a.insert("a", 1);
-        b.insert("a", 1);
+// A child left to visit, along with the depth its location is relative to
+type Pending<'a, K, V, M> = (&'a Child<KV<K, V>, M>, usize);
 
-        a.insert("b", 2);
-        b.insert("b", 3);
+// A stack of the children left to visit in a tree, next child on top
+struct DiffCursor<'a, K, V, M>
+    where K: 'a + Val + Ord,
+          V: 'a + Clone,
+          M: 'a + Meta<KV<K, V>>
+{
+    stack: Vec<Pending<'a, K, V, M>>,
+    stash: &'a Stash<KV<K, V>, M>,
+}
 
-        b.insert("c", 4);
+impl<'a, K, V, M> DiffCursor<'a, K, V, M>
+    where K: 'a + Val + Ord,
+          V: 'a + Clone,
+          M: 'a + Meta<KV<K, V>>
+{
+    fn new(map: &'a Collection<KV<K, V>, M>) -> Self {
+        let mut cursor = DiffCursor {
+            stack: vec![],
+            stash: &map.stash,
+        };
+        cursor.push_node(map.root);
+        cursor
+    }
 
-        let am = a.merge(&mut b);
-        let bm = b.merge(&mut a);
+    fn push_node(&mut self, location: Location<KV<K, V>, M>) {
+        let stash = self.stash;
+        for child in stash.get(location).children.iter().rev() {
+            self.stack.push((child, location.depth));
+        }
+    }
 
-        assert_eq!(am.get("a"), Some(&1));
-        assert_eq!(am.get("b"), Some(&3));
-        assert_eq!(am.get("c"), Some(&4));
+    fn peek(&self) -> Option<&'a Child<KV<K, V>, M>> {
+        self.stack.last().map(|&(child, _)| child)
+    }
 
-        assert_eq!(bm.get("a"), Some(&1));
-        assert_eq!(bm.get("b"), Some(&2));
-        assert_eq!(bm.get("c"), Some(&4));
+    // Replaces the node on top of the stack with its children
+    fn expand(&mut self) {
+        if let Some((&Child::Node { location, .. }, depth)) = self.stack
+            .pop() {
+            self.push_node(location.relative(depth));
+        }
+    }
+
+    // Pops the next leaf, expanding nodes on the way
+    fn next_leaf(&mut self) -> Option<&'a KV<K, V>> {
+        loop {
+            match self.peek() {
+                Some(Child::Leaf(kv)) => {
+                    self.stack.pop();
+                    return Some(kv);
+                }
+                Some(Child::Node { .. }) => self.expand(),
+                None => return None,
+            }
+        }
+    }
+}
+
+impl<K, V, M> MapOpsCheckSum<K, V, M> for Collection<KV<K, V>, M>
+    where M: Meta<KV<K, V>> + SubMeta<Key<K>> + SubMeta<CheckSum<u64>>,
+          K: Val + Ord,
+          V: Clone
+{
+    fn diff(&self, other: &Self) -> Vec<MapChange<K, V>> {
+        let mut changes = vec![];
+        let mut a = DiffCursor::new(self);
+        let mut b = DiffCursor::new(other);
+
+        loop {
+            let (child_a, child_b) = match (a.peek(), b.peek()) {
+                (Some(child_a), Some(child_b)) => (child_a, child_b),
+                (Some(_), None) => {
+                    while let Some(kv) = a.next_leaf() {
+                        changes.push(MapChange::Removed(kv.k.clone(),
+                                                        kv.v.clone()));
+                    }
+                    return changes;
+                }
+                (None, Some(_)) => {
+                    while let Some(kv) = b.next_leaf() {
+                        changes.push(MapChange::Added(kv.k.clone(),
+                                                      kv.v.clone()));
+                    }
+                    return changes;
+                }
+                (None, None) => return changes,
+            };
+
+            let (meta_a, meta_b) = (child_a.meta(), child_b.meta());
+            let key_a: Cow<Key<K>> = (*meta_a).submeta();
+            let key_b: Cow<Key<K>> = (*meta_b).submeta();
+            let sum_a: Cow<CheckSum<u64>> = (*meta_a).submeta();
+            let sum_b: Cow<CheckSum<u64>> = (*meta_b).submeta();
+
+            // Identical subtrees, skip over both
+            if key_a == key_b && sum_a == sum_b {
+                a.stack.pop();
+                b.stack.pop();
+                continue;
+            }
+
+            match (child_a, child_b) {
+                (Child::Leaf(kv_a), Child::Leaf(kv_b)) => {
+                    match kv_a.k.cmp(&kv_b.k) {
+                        Ordering::Less => {
+                            changes.push(MapChange::Removed(kv_a.k.clone(),
+                                                            kv_a.v.clone()));
+                            a.stack.pop();
+                        }
+                        Ordering::Greater => {
+                            changes.push(MapChange::Added(kv_b.k.clone(),
+                                                          kv_b.v.clone()));
+                            b.stack.pop();
+                        }
+                        Ordering::Equal => {
+                            changes.push(MapChange::Modified(kv_a.k.clone(),
+                                                             kv_a.v.clone(),
+                                                             kv_b.v.clone()));
+                            a.stack.pop();
+                            b.stack.pop();
+                        }
+                    }
+                }
+                // Expand the subtree reaching further, or both if they
+                // end at the same key
+                (Child::Node { .. }, Child::Leaf(_)) => a.expand(),
+                (Child::Leaf(_), Child::Node { .. }) => b.expand(),
+                (Child::Node { .. }, Child::Node { .. }) => {
+                    match key_a.cmp(&key_b) {
+                        Ordering::Greater => a.expand(),
+                        Ordering::Less => b.expand(),
+                        Ordering::Equal => {
+                            a.expand();
+                            b.expand();
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn merge3<F>(base: &Self, a: &mut Self, b: &Self, mut resolve: F) -> Self
+        where V: PartialEq,
+              F: FnMut(&K, Option<&V>, Option<&V>, Option<&V>) -> Option<V>
+    {
+        let mut merged = a.clone_mut();
+        let mut ours = base.diff(a).into_iter().map(split_change).peekable();
+        let mut theirs = base.diff(b).into_iter().map(split_change).peekable();
+
+        loop {
+            let order = match (ours.peek(), theirs.peek()) {
+                (Some(o), Some(t)) => o.0.cmp(&t.0),
+                // The changes in `a` are already in `merged`
+                (_, None) => return merged,
+                (None, Some(_)) => Ordering::Greater,
+            };
+            match order {
+                Ordering::Less => {
+                    ours.next();
+                }
+                Ordering::Greater => {
+                    if let Some((k, _, new)) = theirs.next() {
+                        apply_change(&mut merged, k, new);
+                    }
+                }
+                Ordering::Equal => {
+                    if let (Some((k, old, mine)), Some((_, _, yours))) =
+                        (ours.next(), theirs.next()) {
+                        if mine != yours {
+                            let new = resolve(&k,
+                                              old.as_ref(),
+                                              mine.as_ref(),
+                                              yours.as_ref());
+                            apply_change(&mut merged, k, new);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Splits a change into the key, the old value and the new value
+fn split_change<K, V>(change: MapChange<K, V>) -> (K, Option<V>, Option<V>) {
+    match change {
+        MapChange::Added(k, v) => (k, None, Some(v)),
+        MapChange::Removed(k, v) => (k, Some(v), None),
+        MapChange::Modified(k, old, new) => (k, Some(old), Some(new)),
+    }
+}
+
+// Sets the value at key `k` to `v`, removing the key if `v` is `None`
+fn apply_change<K, V, M>(map: &mut Collection<KV<K, V>, M>,
+                         k: K,
+                         v: Option<V>)
+    where M: Meta<KV<K, V>> + SubMeta<Key<K>>,
+          K: Val + Ord,
+          V: Clone
+{
+    match v {
+        Some(v) => map.insert(k, v),
+        None => {
+            map.remove(k);
+        }
+    }
+}
+
+impl<K, V, M> MapOpsPrefix<K, V, M> for Collection<KV<K, V>, M>
+    where M: Meta<KV<K, V>> + SubMeta<Key<K>> + SubMeta<Prefix<u8>>,
+          K: Val + Ord + AsRef<[u8]>,
+          V: Clone
+{
+    fn iter_prefix<'a>(&'a self, prefix: &[u8]) -> PrefixPairs<'a, K, V, M> {
+        PrefixPairs {
+            inner: self.iter_from_using(Prefix::new(prefix)),
+            prefix: Prefix::new(prefix),
+        }
+    }
+}
+
+impl<K, V, M> MapOpsBloom<K, V, M> for Collection<KV<K, V>, M>
+    where M: Meta<KV<K, V>> + SubMeta<Key<K>> + SubMeta<Bloom<K>>,
+          K: Val + Ord,
+          V: Clone
+{
+    fn contains_key_bloom(&self, key: K) -> bool {
+        let mut search = Key::new(key.clone());
+        let mut location = self.root;
+
+        'descend: loop {
+            let node = self.stash.get(location);
+            for child in &node.children {
+                match *child {
+                    Child::Node { location: child_location, ref meta } => {
+                        let key_meta: Cow<Key<K>> = meta.submeta();
+                        match Select::<KV<K, V>>::select(&mut search,
+                                                         key_meta) {
+                            Selection::Miss => (),
+                            Selection::Hit => return true,
+                            Selection::Between => {
+                                let bloom: Cow<Bloom<K>> = meta.submeta();
+                                if !bloom.may_contain(&key) {
+                                    return false;
+                                }
+                                location =
+                                    child_location.relative(location.depth);
+                                continue 'descend;
+                            }
+                        }
+                    }
+                    Child::Leaf(ref kv) => {
+                        match kv.key().cmp(&key) {
+                            Ordering::Less => (),
+                            Ordering::Equal => return true,
+                            Ordering::Greater => return false,
+                        }
+                    }
+                }
+            }
+            return false;
+        }
+    }
+}
+
+impl<K, V, M> MapOpsValueMax<K, V, M> for Collection<KV<K, V>, M>
+    where M: Meta<KV<K, V>> + SubMeta<Key<K>> + SubMeta<ValueMax<V>>,
+          K: Val + Ord,
+          V: Clone + Ord
+{
+    fn max_by_value(&self) -> Option<(&K, &V)> {
+        let mut location = self.root;
+
+        loop {
+            let node = self.stash.get(location);
+            let mut best: Option<&Child<KV<K, V>, M>> = None;
+            let mut best_value = None;
+            for child in &node.children {
+                let value = match *child {
+                    Child::Node { ref meta, .. } => {
+                        let max: Cow<ValueMax<V>> = meta.submeta();
+                        max.inner().clone()
+                    }
+                    Child::Leaf(ref kv) => kv.v.clone(),
+                };
+                if best_value.as_ref().is_none_or(|best| value > *best) {
+                    best = Some(child);
+                    best_value = Some(value);
+                }
+            }
+            match best {
+                Some(Child::Node { location: child_location, .. }) => {
+                    location = child_location.relative(location.depth);
+                }
+                Some(Child::Leaf(kv)) => return Some((&kv.k, &kv.v)),
+                None => return None,
+            }
+        }
+    }
+
+    fn find_by_value(&self, range: Range<V>) -> Vec<(&K, &V)> {
+        let mut found = vec![];
+        by_value(self, self.root, &range, &mut found);
+        found
+    }
+}
+
+// Collects the pairs below `location` with values in `range` into
+// `found`, skipping subtrees where no value reaches the range.
+fn by_value<'a, K, V, M>(map: &'a Collection<KV<K, V>, M>,
+                         location: Location<KV<K, V>, M>,
+                         range: &Range<V>,
+                         found: &mut Vec<(&'a K, &'a V)>)
+    where M: Meta<KV<K, V>> + SubMeta<ValueMax<V>>,
+          K: Val + Ord,
+          V: Clone + Ord
+{
+    let node = map.stash.get(location);
+    for child in &node.children {
+        match *child {
+            Child::Node { location: child_location, ref meta } => {
+                let max: Cow<ValueMax<V>> = meta.submeta();
+                if *max.inner() >= range.start {
+                    by_value(map,
+                             child_location.relative(location.depth),
+                             range,
+                             found);
+                }
+            }
+            Child::Leaf(ref kv) => {
+                if kv.v >= range.start && kv.v < range.end {
+                    found.push((&kv.k, &kv.v));
+                }
+            }
+        }
+    }
+}
+
+impl<K, V, M> MapOpsCardinality<K, V, M> for Collection<KV<K, V>, M>
+    where M: Meta<KV<K, V>> + SubMeta<Key<K>> + SubMeta<Cardinality<usize>>,
+          K: Val + Ord,
+          V: Clone
+{
+    fn count_range(&self, from: K, to: K) -> usize {
+        let before_to = self.count_before(Key::new(to));
+        let before_from = self.count_before(Key::new(from));
+        before_to.saturating_sub(before_from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate rand;
+
+    const LOTS: usize = 100_000;
+
+    use std::hash::{Hash, Hasher};
+    use std::cmp::Ordering;
+    use std::collections::BTreeMap;
+
+    use meta::key::{Key, Keyed, KeySum, ValSum};
+
+    use collection::Collection;
+
+    use super::{MapOps, KV};
+    use super::MapOpsKeySum;
+    use super::MapOpsCardinality;
+    use super::MapOpsPrefix;
+    use super::MapOpsBloom;
+    use super::MapOpsValueMax;
+    use super::{MapOpsCheckSum, MapChange};
+
+    collection!(Map<T> {
+        key: Key<T::Key>,
+        keysum: KeySum<u64>,
+        valsum: ValSum<u64>,
+    } where T: Keyed, T::Key: Hash, T::Value: Hash);
+
+    mod counted {
+        use std::hash::Hash;
+
+        use meta::key::{Key, Keyed};
+        use meta::cardinality::Cardinality;
+
+        use collection::Collection;
+
+        collection!(CountedMap<T> {
+            key: Key<T::Key>,
+            cardinality: Cardinality<usize>,
+        } where T: Keyed, T::Key: Hash);
+    }
+
+    use self::counted::CountedMap;
+
+    mod prefixed {
+        use meta::key::{Key, Keyed};
+        use meta::prefix::Prefix;
+
+        use collection::Collection;
+
+        collection!(PrefixMap<T> {
+            key: Key<T::Key>,
+            prefix: Prefix<u8>,
+        } where T: Keyed, T::Key: AsRef<[u8]>);
+    }
+
+    use self::prefixed::PrefixMap;
+
+    mod bloomed {
+        use std::hash::Hash;
+
+        use meta::key::{Key, Keyed};
+        use meta::bloom::Bloom;
+
+        use collection::Collection;
+
+        collection!(BloomMap<T> {
+            key: Key<T::Key>,
+            bloom: Bloom<T::Key>,
+        } where T: Keyed, T::Key: Hash);
+    }
+
+    use self::bloomed::BloomMap;
+
+    mod valued {
+        use std::hash::Hash;
+
+        use meta::key::{Key, Keyed};
+        use meta::value_max::ValueMax;
+
+        use collection::Collection;
+
+        collection!(ValueMap<T> {
+            key: Key<T::Key>,
+            value_max: ValueMax<T::Value>,
+        } where T: Keyed, T::Key: Hash, T::Value: Ord);
+    }
+
+    use self::valued::ValueMap;
+
+    // A key that compares without regard to case
+    #[derive(Clone, Debug)]
+    struct NoCase(&'static str);
+
+    impl NoCase {
+        fn folded(&self) -> String {
+            self.0.to_lowercase()
+        }
+    }
+
+    impl Hash for NoCase {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.folded().hash(state)
+        }
+    }
+
+    impl PartialEq for NoCase {
+        fn eq(&self, other: &Self) -> bool {
+            self.folded() == other.folded()
+        }
+    }
+
+    impl Eq for NoCase {}
+
+    impl PartialOrd for NoCase {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for NoCase {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.folded().cmp(&other.folded())
+        }
+    }
+
+    #[test]
+    fn insert() {
+        let mut map = Map::new();
+        map.insert("a", 1);
+        assert_eq!(map.get("a"), Some(&1));
+    }
+
+    #[test]
+    fn partial_eq() {
+        let mut a = Map::new();
+        let mut b = Map::new();
+
+        for i in 0..LOTS {
+            a.insert(i, i + 1);
+            b.insert(LOTS - i - 1, LOTS - i - 1);
+        }
+
+        // mutate in a
+        for i in 0..LOTS {
+            a.get_mut(i).map(|mut v| *v -= 1);
+        }
+
+        assert!(a == b);
+    }
+
+    #[test]
+    fn count_range() {
+        let mut map = CountedMap::new();
+
+        for i in 0..LOTS {
+            map.insert(i * 3, i);
+        }
+
+        assert_eq!(map.count_range(0, LOTS * 3), LOTS);
+        assert_eq!(map.count_range(1, 3), 0);
+        assert_eq!(map.count_range(1, 4), 1);
+        assert_eq!(map.count_range(30, 60), 10);
+        assert_eq!(map.count_range(LOTS * 3 - 3, LOTS * 3), 1);
+    }
+
+    #[test]
+    fn keys_values() {
+        let mut map = Map::new();
+
+        for i in 0..LOTS {
+            map.insert(LOTS - i - 1, i);
+        }
+
+        let mut keys = map.keys();
+        let mut values = map.values();
+
+        for i in 0..LOTS {
+            assert_eq!(keys.next(), Some(&i));
+            assert_eq!(values.next(), Some(&(LOTS - i - 1)));
+        }
+        assert_eq!(keys.next(), None);
+        assert_eq!(values.next(), None);
+    }
+
+    #[test]
+    fn iter_pairs() {
+        let mut map = Map::new();
+
+        for i in 0..LOTS {
+            map.insert(i, i * 2);
+        }
+
+        let mut count = 0;
+        for (k, v) in map.iter_pairs() {
+            assert_eq!(*k, count);
+            assert_eq!(*v, count * 2);
+            count += 1;
+        }
+        assert_eq!(count, LOTS);
+    }
+
+    #[test]
+    fn std_map() {
+        let std: BTreeMap<usize, usize> = (0..LOTS)
+            .map(|i| (i * 7 % LOTS, i))
+            .collect();
+
+        let map = Map::from(std.clone());
+        let mut reference = Map::new();
+        for i in 0..LOTS {
+            reference.insert(i * 7 % LOTS, i);
+        }
+        assert!(map == reference);
+        assert_eq!(BTreeMap::from(&map), std);
+    }
+
+    #[test]
+    fn extend() {
+        let mut map = Map::new();
+        let mut reference = Map::new();
+
+        for i in 0..LOTS {
+            reference.insert(i, i + 1);
+        }
+
+        map.extend((0..LOTS).map(|i| (i, i)));
+        map.extend((0..LOTS).rev().map(|i| (i, i + 1)));
+        map.extend(None);
+        assert!(map == reference);
+    }
+
+    #[test]
+    fn first_last() {
+        let mut map = Map::new();
+
+        assert_eq!(map.first_key_value(), None);
+        assert_eq!(map.last_key_value(), None);
+        assert_eq!(map.pop_first(), None);
+        assert_eq!(map.pop_last(), None);
+
+        for i in 0..LOTS {
+            map.insert(i, i + 1);
+        }
+
+        for i in 0..LOTS / 2 {
+            assert_eq!(map.first_key_value(), Some((&i, &(i + 1))));
+            let last = LOTS - i - 1;
+            assert_eq!(map.last_key_value(), Some((&last, &(last + 1))));
+
+            assert_eq!(map.pop_first(), Some((i, i + 1)));
+            assert_eq!(map.pop_last(), Some((last, last + 1)));
+        }
+
+        assert_eq!(map.pop_first(), None);
+        assert_eq!(map.pop_last(), None);
+        assert!(map == Map::new());
+    }
+
+    #[test]
+    fn contains_key() {
+        let mut map = Map::new();
+
+        for i in 0..LOTS / 2 {
+            map.insert(i * 2, i);
+        }
+
+        for i in 0..LOTS {
+            assert_eq!(map.contains_key(i), i % 2 == 0);
+        }
+    }
+
+    #[test]
+    fn overwrite() {
+        let mut map = Map::new();
+
+        map.insert("a", 1);
+        assert_eq!(map.get("a"), Some(&1));
+        map.insert("a", 2);
+        assert_eq!(map.get("a"), Some(&2));
+    }
+
+    #[test]
+    fn clone() {
+        let mut a = Map::new();
+
+        a.insert("a", 1);
+
+        let mut b = a.clone_mut();
+
+        b.insert("a", 2);
+
+        assert_eq!(a.get("a"), Some(&1));
+        assert_eq!(b.get("a"), Some(&2));
+    }
+
+    #[test]
+    fn merge() {
+        let mut a = Map::new();
+        let mut b = Map::new();
+
+        a.insert("a", 1);
+        b.insert("a", 1);
+
+        a.insert("b", 2);
+        b.insert("b", 3);
+
+        b.insert("c", 4);
+
+        let am = a.merge(&mut b);
+        let bm = b.merge(&mut a);
+
+        assert_eq!(am.get("a"), Some(&1));
+        assert_eq!(am.get("b"), Some(&3));
+        assert_eq!(am.get("c"), Some(&4));
+
+        assert_eq!(bm.get("a"), Some(&1));
+        assert_eq!(bm.get("b"), Some(&2));
+        assert_eq!(bm.get("c"), Some(&4));
+    }
+
+    #[test]
+    fn merge_with() {
+        let mut a = Map::new();
+        let mut b = Map::new();
+
+        for i in 0..LOTS {
+            a.insert(i * 2, 1);
+            b.insert(i * 3, 2);
+        }
+
+        let mut merged = a.merge_with(&mut b, |x, y| x + y);
+
+        for i in 0..LOTS * 3 {
+            let expected = match (i % 2 == 0 && i < LOTS * 2, i % 3 == 0) {
+                (true, true) => Some(&3),
+                (true, false) => Some(&1),
+                (false, true) => Some(&2),
+                (false, false) => None,
+            };
+            assert_eq!(merged.get(i), expected);
+        }
+
+        // the inputs are left untouched
+        assert_eq!(a.get(0), Some(&1));
+        assert_eq!(b.get(0), Some(&2));
+
+        // and the result is the same as building it by hand
+        let mut by_hand = Map::new();
+        for (k, v) in a.iter_pairs().chain(b.iter_pairs()) {
+            let old = by_hand.get(*k).cloned().unwrap_or(0);
+            by_hand.insert(*k, old + v);
+        }
+        assert!(merged == by_hand);
+        merged.insert(0, 0);
+        assert!(merged != by_hand);
+    }
+
+    #[test]
+    fn checksum_keys_values() {
+        let mut a = Map::new();
+        let mut b = Map::new();
+
+        // Equal with the earlier `k ^ v`, where keys and values could
+        // trade places
+        a.insert(1, 2);
+        b.insert(2, 1);
+        assert!(a != b);
+    }
+
+    #[test]
+    fn diff() {
+        let mut a = Map::new();
+
+        for i in 0..LOTS {
+            a.insert(i, i);
+        }
+
+        let mut b = a.clone_mut();
+
+        assert_eq!(a.diff(&b), vec![]);
+
+        b.remove(10);
+        b.insert(LOTS, 0);
+        b.insert(LOTS / 2, 0);
+        if let Some(mut v) = b.get_mut(1000) {
+            *v += 1;
+        }
+
+        assert_eq!(a.diff(&b),
+                   vec![MapChange::Removed(10, 10),
+                        MapChange::Modified(1000, 1000, 1001),
+                        MapChange::Modified(LOTS / 2, LOTS / 2, 0),
+                        MapChange::Added(LOTS, 0)]);
+
+        assert_eq!(b.diff(&a),
+                   vec![MapChange::Added(10, 10),
+                        MapChange::Modified(1000, 1001, 1000),
+                        MapChange::Modified(LOTS / 2, 0, LOTS / 2),
+                        MapChange::Removed(LOTS, 0)]);
+
+        let empty = Map::new();
+        assert_eq!(empty.diff(&empty), vec![]);
+        assert_eq!(a.diff(&empty).len(), LOTS);
+        assert_eq!(empty.diff(&b).len(), LOTS);
+    }
+
+    #[test]
+    fn merge3() {
+        let mut base = Map::new();
+
+        for i in 0..LOTS {
+            base.insert(i, i);
+        }
+
+        let mut a = base.clone_mut();
+        let mut b = base.clone_mut();
+
+        a.insert(1, 10);
+        a.remove(2);
+        a.insert(5, 50);
+        a.insert(LOTS, 0);
+
+        b.insert(1, 11);
+        b.remove(2);
+        b.insert(3, 30);
+        b.remove(4);
+        b.insert(5, 50);
+        b.insert(LOTS + 1, 0);
+
+        let mut conflicts = vec![];
+        let merged = Map::merge3(&base, &mut a, &b, |k, old, mine, yours| {
+            conflicts.push((*k, old.cloned(), mine.cloned(), yours.cloned()));
+            mine.cloned().max(yours.cloned())
+        });
+
+        assert_eq!(conflicts, vec![(1, Some(1), Some(10), Some(11))]);
+
+        assert_eq!(merged.get(1), Some(&11));
+        assert_eq!(merged.get(2), None);
+        assert_eq!(merged.get(3), Some(&30));
+        assert_eq!(merged.get(4), None);
+        assert_eq!(merged.get(5), Some(&50));
+        assert_eq!(merged.get(6), Some(&6));
+        assert_eq!(merged.get(LOTS), Some(&0));
+        assert_eq!(merged.get(LOTS + 1), Some(&0));
+
+        // The inputs are left as they were
+        assert_eq!(base.get(1), Some(&1));
+        assert_eq!(a.get(1), Some(&10));
+        assert_eq!(a.get(3), Some(&3));
+
+        // Removing on one side and modifying on the other is a conflict
+        let mut a = base.clone_mut();
+        let mut b = base.clone_mut();
+        a.remove(7);
+        b.insert(7, 70);
+        let merged = Map::merge3(&base, &mut a, &b, |_, _, _, _| None);
+        assert_eq!(merged.get(7), None);
+
+        // Without changes on either side, the merge is the base
+        let mut a = base.clone_mut();
+        let merged = Map::merge3(&base, &mut a, &base, |_, _, _, _| None);
+        assert!(merged.diff(&base).is_empty());
+    }
+
+    #[test]
+    fn retain() {
+        let mut a = Map::new();
+        let mut reference = Map::new();
+
+        for i in 0..LOTS {
+            a.insert(i, i % 7);
+            if i % 7 != 3 {
+                reference.insert(i, i % 7);
+            }
+        }
+
+        let mut b = a.clone_mut();
+        b.retain(|_, v| *v != 3);
+
+        assert!(b == reference);
+        assert_eq!(a.get(3), Some(&3));
+        assert_eq!(b.get(3), None);
+
+        b.retain(|_, _| false);
+        assert!(b == Map::new());
+    }
+
+    #[test]
+    fn get_or_insert_with() {
+        let mut map = Map::new();
+        let mut reference = Map::new();
+
+        for i in 0..LOTS {
+            let k = (i * 7919) % LOTS;
+            assert_eq!(*map.get_or_insert_with(k, || k + 1), k + 1);
+            reference.insert(k, k + 1);
+        }
+
+        for i in 0..LOTS {
+            assert_eq!(*map.get_or_insert_with(i, || unreachable!()), i + 1);
+        }
+
+        assert!(map == reference);
+    }
+
+    #[test]
+    fn from_sorted_pairs() {
+        let mut reference = Map::new();
+
+        for i in 0..LOTS {
+            reference.insert(i, i * 2);
+        }
+
+        let mut map = Map::from_sorted_pairs((0..LOTS).map(|i| (i, i * 2)));
+
+        assert!(map == reference);
+        for i in 0..LOTS {
+            assert_eq!(map.get(i), Some(&(i * 2)));
+        }
+
+        map.insert(LOTS, 0);
+        reference.insert(LOTS, 0);
+        assert!(map == reference);
+
+        for n in 0..100 {
+            let mut small = Map::new();
+            for i in 0..n {
+                small.insert(i, i);
+            }
+            assert!(Map::from_sorted_pairs((0..n).map(|i| (i, i))) == small);
+        }
+    }
+
+    #[test]
+    fn get_key_value() {
+        let mut map = Map::new();
+
+        map.insert(NoCase("Apple"), 1);
+        map.insert(NoCase("banana"), 2);
+
+        let (k, v) = map.get_key_value(NoCase("APPLE")).unwrap();
+        assert_eq!(k.0, "Apple");
+        assert_eq!(*v, 1);
+
+        let (k, v) = map.get_key_value(NoCase("Banana")).unwrap();
+        assert_eq!(k.0, "banana");
+        assert_eq!(*v, 2);
+
+        assert!(map.get_key_value(NoCase("cherry")).is_none());
+    }
+
+    #[test]
+    fn intersect_difference_keys() {
+        let mut a = Map::new();
+        let mut b = Map::new();
+        let mut intersection = Map::new();
+        let mut difference = Map::new();
+
+        for i in 0..LOTS {
+            a.insert(i, i);
+            if i % 3 == 0 {
+                b.insert(i, 0);
+                intersection.insert(i, i);
+            } else {
+                difference.insert(i, i);
+            }
+        }
+        // keys only in b
+        for i in LOTS..LOTS + 100 {
+            b.insert(i, 0);
+        }
+
+        assert!(a.intersect_keys(&mut b) == intersection);
+        assert!(a.difference_keys(&mut b) == difference);
+
+        // sharing most of the tree
+        let mut c = a.clone_mut();
+        c.remove(LOTS / 2);
+        c.insert(LOTS, 0);
+
+        let mut only_a = Map::new();
+        only_a.insert(LOTS / 2, LOTS / 2);
+
+        let mut shared = a.clone_mut();
+        shared.remove(LOTS / 2);
+
+        assert!(a.difference_keys(&mut c) == only_a);
+        assert!(a.intersect_keys(&mut c) == shared);
+
+        let mut empty = Map::new();
+        assert!(a.intersect_keys(&mut empty) == Map::new());
+        assert!(a.difference_keys(&mut empty) == a);
+        assert!(empty.difference_keys(&mut a) == Map::new());
+    }
+
+    #[test]
+    fn map_values() {
+        let mut map = Map::new();
+        let mut reference = Map::new();
+
+        for i in 0..LOTS {
+            map.insert(i, i);
+            reference.insert(i, format!("{}", i * 2));
+        }
+
+        let mapped: Map<KV<usize, String>> = map.map_values(|v| {
+            format!("{}", v * 2)
+        });
+
+        assert!(mapped == reference);
+        assert_eq!(mapped.get(21), Some(&String::from("42")));
+    }
+
+    #[test]
+    fn zip_by_key() {
+        let mut a = Map::new();
+        let mut b = Map::new();
+
+        for i in 0..LOTS {
+            if i % 2 == 0 {
+                a.insert(i, i);
+            }
+            if i % 3 == 0 {
+                b.insert(i, format!("{}", i));
+            }
+        }
+
+        let mut count = 0;
+        for (i, (k, va, vb)) in (0..LOTS)
+            .filter(|i| i % 2 == 0 || i % 3 == 0)
+            .zip(a.zip_by_key(&b)) {
+            assert_eq!(*k, i);
+            assert_eq!(va, if i % 2 == 0 { Some(&i) } else { None });
+            assert_eq!(vb.is_some(), i % 3 == 0);
+            if let Some(v) = vb {
+                assert_eq!(*v, format!("{}", i));
+            }
+            count += 1;
+        }
+        assert_eq!(a.zip_by_key(&b).count(), count);
+
+        let empty: Map<KV<usize, String>> = Map::new();
+        assert_eq!(a.zip_by_key(&empty).count(), LOTS / 2);
+    }
+
+    #[test]
+    fn remove_entry() {
+        let mut map = Map::new();
+
+        map.insert(NoCase("Apple"), 1);
+        map.insert(NoCase("banana"), 2);
+
+        let (k, v) = map.remove_entry(NoCase("APPLE")).unwrap();
+        assert_eq!(k.0, "Apple");
+        assert_eq!(v, 1);
+
+        assert!(map.remove_entry(NoCase("apple")).is_none());
+        assert_eq!(map.remove(NoCase("BANANA")), Some(2));
+        assert!(map == Map::new());
+    }
+
+    #[test]
+    fn len() {
+        let mut map = CountedMap::new();
+
+        assert_eq!(map.len(), 0);
+        assert!(map.is_empty());
+
+        for i in 0..LOTS {
+            map.insert(i, i);
+            // overwriting does not change the length
+            map.insert(i / 2, i);
+        }
+        assert_eq!(map.len(), LOTS);
+
+        for i in 0..LOTS / 2 {
+            map.remove(i * 2);
+        }
+        assert_eq!(map.len(), LOTS / 2);
+        assert!(!map.is_empty());
+    }
+
+    #[test]
+    fn iter_prefix() {
+        let mut map = PrefixMap::new();
+
+        for i in 0..LOTS {
+            map.insert(format!("{}", i), i);
+        }
+        map.insert(String::from("1"), 1);
+        map.insert(String::from("12"), 12);
+
+        let found: Vec<_> = map.iter_prefix(b"123").map(|(_, v)| *v).collect();
+        let mut expected: Vec<_> = (0..LOTS)
+            .filter(|i| format!("{}", i).starts_with("123"))
+            .collect();
+        expected.sort_by_key(|i| format!("{}", i));
+        assert_eq!(found, expected);
+
+        // the shorter keys "1" and "12" go before, but do not match
+        assert_eq!(map.iter_prefix(b"12").next(),
+                   Some((&String::from("12"), &12)));
+        assert_eq!(map.iter_prefix(b"").count(), LOTS);
+        assert_eq!(map.iter_prefix(b"x").next(), None);
+        assert_eq!(map.iter_prefix(b"99999").count(), 1);
+        assert_eq!(map.iter_prefix(b"999999").next(), None);
+    }
+
+    #[test]
+    fn contains_key_bloom() {
+        let mut map = BloomMap::new();
+
+        for i in 0..LOTS {
+            map.insert(i * 2, i);
+        }
+
+        for i in 0..LOTS * 2 + 1 {
+            assert_eq!(map.contains_key_bloom(i), i % 2 == 0 && i < LOTS * 2);
+        }
+
+        map.remove(4);
+        assert!(!map.contains_key_bloom(4));
+        assert!(!BloomMap::<KV<usize, usize>>::new().contains_key_bloom(0));
+    }
+
+    #[test]
+    fn by_value() {
+        let mut map = ValueMap::new();
+
+        assert_eq!(map.max_by_value(), None);
+
+        for i in 0..LOTS {
+            map.insert(i, (i * 7919) % LOTS);
+        }
+        let top = *map.iter_pairs()
+            .find(|&(_, v)| *v == LOTS - 1)
+            .unwrap()
+            .0;
+        assert_eq!(map.max_by_value(), Some((&top, &(LOTS - 1))));
+
+        let found = map.find_by_value(LOTS - 100..LOTS - 90);
+        assert_eq!(found.len(), 10);
+        assert!(found.windows(2).all(|w| w[0].0 < w[1].0));
+        for &(k, v) in &found {
+            assert_eq!((k * 7919) % LOTS, *v);
+            assert!(*v >= LOTS - 100 && *v < LOTS - 90);
+        }
+
+        map.remove(top);
+        assert_eq!(map.max_by_value().map(|(_, v)| *v), Some(LOTS - 2));
+        assert_eq!(map.find_by_value(LOTS - 1..LOTS), vec![]);
+
+        // Ties go to the first key
+        map.insert(LOTS, LOTS - 2);
+        assert!(*map.max_by_value().unwrap().0 < LOTS);
+    }
+
+    #[test]
+    fn from_iter() {
+        let mut reference = Map::new();
+
+        for i in 0..LOTS {
+            reference.insert(i, i);
+        }
+
+        let map: Map<_> = (0..LOTS)
+            .rev()
+            .map(|i| (i, 0))
+            .chain((0..LOTS).map(|i| (i, i)))
+            .collect();
+
+        assert!(map == reference);
+        assert_eq!(map.get(LOTS / 2), Some(&(LOTS / 2)));
+
+        let kv: KV<_, _> = ("a", 1).into();
+        assert_eq!(kv.key(), &"a");
     }
 }
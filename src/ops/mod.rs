@@ -1,3 +1,11 @@
 pub mod set;
 pub mod vector;
 pub mod map;
+pub mod multiset;
+pub mod union_view;
+pub mod multimap;
+pub mod ord_by;
+pub mod secondary_index;
+pub mod interval;
+pub mod versioned;
+pub mod history;
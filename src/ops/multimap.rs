@@ -0,0 +1,128 @@
+use Val;
+
+use collection::Collection;
+
+use meta::{Meta, SubMeta};
+use meta::key::Key;
+
+use ops::map::{KV, MapOps};
+
+/// Multimap operations on a Collection, mapping each key to a list of
+/// values
+pub trait MultiMapOps<K, V>
+    where Self: Sized
+{
+    /// Add the value `V` to the values at key `K`
+    fn insert(&mut self, key: K, val: V);
+    /// Get all values at key `K`, in insertion order
+    fn get_all(&self, key: K) -> &[V];
+    /// Remove one occurrence of `val` from the values at key `K`,
+    /// returning `true` if there was one to remove.
+    fn remove_one(&mut self, key: K, val: &V) -> bool;
+}
+
+impl<K, V, M> MultiMapOps<K, V> for Collection<KV<K, Vec<V>>, M>
+    where M: Meta<KV<K, Vec<V>>> + SubMeta<Key<K>>,
+          K: Val + Ord,
+          V: Clone + PartialEq
+{
+    fn insert(&mut self, key: K, val: V) {
+        let mut val = Some(val);
+        if let Some(mut vals) = self.get_mut(key.clone()) {
+            vals.push(val.take().expect("not taken"));
+        }
+        if let Some(val) = val {
+            MapOps::insert(self, key, vec![val]);
+        }
+    }
+
+    fn get_all(&self, key: K) -> &[V] {
+        match self.get(key) {
+            Some(vals) => vals,
+            None => &[],
+        }
+    }
+
+    fn remove_one(&mut self, key: K, val: &V) -> bool {
+        let (removed, now_empty) = match self.get_mut(key.clone()) {
+            Some(mut vals) => {
+                match vals.iter().position(|v| v == val) {
+                    Some(i) => {
+                        vals.remove(i);
+                        (true, vals.is_empty())
+                    }
+                    None => (false, false),
+                }
+            }
+            None => (false, false),
+        };
+        if now_empty {
+            MapOps::remove(self, key);
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    const LOTS: usize = 100_000;
+
+    use std::hash::Hash;
+
+    use meta::key::{Key, Keyed, KeySum, ValSum};
+
+    use collection::Collection;
+
+    use super::MultiMapOps;
+
+    collection!(MultiMap<T> {
+        key: Key<T::Key>,
+        keysum: KeySum<u64>,
+        valsum: ValSum<u64>,
+    } where T: Keyed, T::Key: Hash, T::Value: Hash);
+
+    #[test]
+    fn insert_get_all() {
+        let mut map = MultiMap::new();
+
+        for i in 0..LOTS {
+            map.insert(i % 1000, i);
+        }
+
+        for k in 0..1000 {
+            let vals = map.get_all(k);
+            assert_eq!(vals.len(), LOTS / 1000);
+            for (n, v) in vals.iter().enumerate() {
+                assert_eq!(*v, n * 1000 + k);
+            }
+        }
+        assert_eq!(map.get_all(1000), &[]);
+    }
+
+    #[test]
+    fn remove_one() {
+        let mut map = MultiMap::new();
+        let mut reference = MultiMap::new();
+
+        for i in 0..LOTS {
+            map.insert(i / 2, i % 2);
+            if i % 4 == 0 {
+                reference.insert(i / 2, 0);
+            }
+        }
+
+        for k in 0..LOTS / 2 {
+            if k % 2 == 0 {
+                assert!(map.remove_one(k, &1));
+                assert!(!map.remove_one(k, &1));
+            } else {
+                assert!(map.remove_one(k, &0));
+                assert!(map.remove_one(k, &1));
+                assert!(!map.remove_one(k, &0));
+                assert_eq!(map.get_all(k), &[]);
+            }
+        }
+
+        assert!(map == reference);
+    }
+}
@@ -0,0 +1,268 @@
+use std::borrow::Cow;
+
+use Val;
+
+use collection::Collection;
+
+use meta::{Meta, SubMeta};
+use meta::key::{Key, Keyed};
+use meta::multiplicity::Multiplicity;
+
+use tree::branch::{Branch, BranchResult};
+use tree::level::{Beginning, End};
+use tree::weight::Weight;
+
+/// An element, along with the number of times it occurs
+#[derive(Clone, Debug)]
+pub struct Counted<T>
+    where T: Val + Ord
+{
+    t: T,
+    count: usize,
+}
+
+impl<T> Counted<T>
+    where T: Val + Ord
+{
+    fn new(t: T) -> Self {
+        Counted { t: t, count: 1 }
+    }
+    /// Returns a reference to the element
+    pub fn element(&self) -> &T {
+        &self.t
+    }
+    /// Returns the number of times the element occurs
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl<T> Keyed for Counted<T>
+    where T: Val + Ord
+{
+    type Key = T;
+    type Value = usize;
+    fn key(&self) -> &T {
+        &self.t
+    }
+    fn value(&self) -> &usize {
+        &self.count
+    }
+    fn value_mut(&mut self) -> &mut usize {
+        &mut self.count
+    }
+}
+
+// The count does not affect the weight, so changing it never
+// re-balances the tree.
+impl<T> Weight for Counted<T>
+    where T: Val + Ord
+{
+    fn weight_hash(&self) -> u64 {
+        self.t.weight_hash()
+    }
+}
+
+/// Multiset operations on a Collection
+pub trait MultiSetOps<T>
+    where Self: Sized
+{
+    /// Insert an occurrence of element into the multiset
+    fn insert(&mut self, t: T);
+    /// Insert an occurrence of each element of `iter` into the multiset
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I);
+    /// Remove one occurrence of element from the multiset, returning
+    /// `true` if there was one to remove.
+    fn remove(&mut self, t: &T) -> bool;
+    /// How many times does element occur in this multiset?
+    fn count(&self, t: &T) -> usize;
+}
+
+/// Operations on multisets with `Multiplicity` metadata
+pub trait MultiSetOpsMultiplicity<T>
+    where Self: MultiSetOps<T>
+{
+    /// The total number of occurrences of all elements
+    fn total(&self) -> usize;
+}
+
+impl<T, M> MultiSetOps<T> for Collection<Counted<T>, M>
+    where T: Val + Ord,
+          M: Meta<Counted<T>> + SubMeta<Key<T>>
+{
+    fn insert(&mut self, t: T) {
+        let mut search = Key::new(t.clone());
+        let branch = Branch::<_, _, Beginning>::new_full(self.root,
+                                                         &mut search,
+                                                         &self.stash);
+        match branch {
+            BranchResult::Between(mut b) => {
+                b.insert(Counted::new(t), self.divisor, &mut self.stash);
+                self.root = b.root();
+            }
+            // Already there, count it
+            BranchResult::Hit(mut b) => {
+                if let Some(c) = b.leaf_mut(&mut self.stash) {
+                    c.count += 1;
+                }
+                b.propagate(&mut self.stash);
+                self.root = b.root();
+            }
+            // At the very end
+            BranchResult::Miss => {
+                let mut branch: Branch<_, _, End> = Branch::first(self.root,
+                                                                  &self.stash);
+                branch.insert(Counted::new(t), self.divisor, &mut self.stash);
+                self.root = branch.root();
+            }
+        }
+    }
+
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for t in iter {
+            self.insert(t);
+        }
+    }
+
+    fn remove(&mut self, t: &T) -> bool {
+        let mut search = Key::new(t.clone());
+        let branch = Branch::<_, _, Beginning>::new_full(self.root,
+                                                         &mut search,
+                                                         &self.stash);
+        match branch {
+            BranchResult::Between(_) |
+            BranchResult::Miss => false,
+            BranchResult::Hit(mut b) => {
+                let count = b.leaf(&self.stash).map(|c| c.count).unwrap_or(0);
+                if count > 1 {
+                    if let Some(c) = b.leaf_mut(&mut self.stash) {
+                        c.count -= 1;
+                    }
+                    b.propagate(&mut self.stash);
+                } else {
+                    b.remove(self.divisor, &mut self.stash);
+                }
+                self.root = b.root();
+                true
+            }
+        }
+    }
+
+    fn count(&self, t: &T) -> usize {
+        let mut search = Key::new(t.clone());
+        let res: BranchResult<_, _, Beginning> =
+            Branch::new_full(self.root, &mut search, &self.stash);
+
+        match res {
+            BranchResult::Hit(branch) => {
+                branch.leaf(&self.stash).map(|c| c.count).unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+}
+
+impl<T, M> MultiSetOpsMultiplicity<T> for Collection<Counted<T>, M>
+    where T: Val + Ord,
+          M: Meta<Counted<T>> + SubMeta<Key<T>> + SubMeta<Multiplicity<usize>>
+{
+    fn total(&self) -> usize {
+        match self.stash.get(self.root).meta() {
+            Some(meta) => {
+                let m: Cow<Multiplicity<usize>> = (*meta).submeta();
+                *m.inner()
+            }
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    const LOTS: usize = 100_000;
+
+    use std::hash::Hash;
+
+    use meta::key::{Key, Keyed};
+    use meta::multiplicity::Multiplicity;
+
+    use collection::Collection;
+
+    use super::{MultiSetOps, MultiSetOpsMultiplicity};
+
+    collection!(Bag<T> {
+        key: Key<T::Key>,
+        multiplicity: Multiplicity<usize>,
+    } where T: Keyed<Value = usize>, T::Key: Hash);
+
+    #[test]
+    fn insert_count() {
+        let mut bag = Bag::new();
+
+        for i in 0..LOTS {
+            for _ in 0..i % 4 {
+                bag.insert(i);
+            }
+        }
+
+        for i in 0..LOTS {
+            assert_eq!(bag.count(&i), i % 4);
+        }
+        assert_eq!(bag.total(), (LOTS / 4) * 6);
+    }
+
+    #[test]
+    fn extend() {
+        let mut bag = Bag::new();
+
+        bag.extend(0..LOTS);
+        bag.extend((0..LOTS).filter(|i| i % 2 == 0));
+        bag.extend(None);
+
+        for i in 0..LOTS {
+            assert_eq!(bag.count(&i), 2 - i % 2);
+        }
+        assert_eq!(bag.total(), LOTS + LOTS / 2);
+    }
+
+    #[test]
+    fn remove() {
+        let mut bag = Bag::new();
+
+        for i in 0..LOTS {
+            bag.insert(i);
+            bag.insert(i);
+        }
+        assert_eq!(bag.total(), LOTS * 2);
+
+        for i in 0..LOTS {
+            assert!(bag.remove(&i));
+        }
+        assert_eq!(bag.total(), LOTS);
+
+        for i in 0..LOTS {
+            assert_eq!(bag.count(&i), 1);
+            assert!(bag.remove(&i));
+            assert!(!bag.remove(&i));
+        }
+
+        assert_eq!(bag.total(), 0);
+        assert!(bag.iter().next().is_none());
+    }
+
+    #[test]
+    fn clone() {
+        let mut a = Bag::new();
+
+        a.insert("a");
+
+        let mut b = a.clone_mut();
+
+        b.insert("a");
+
+        assert_eq!(a.count(&"a"), 1);
+        assert_eq!(b.count(&"a"), 2);
+        assert_eq!(a.total(), 1);
+        assert_eq!(b.total(), 2);
+    }
+}
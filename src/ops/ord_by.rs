@@ -0,0 +1,186 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+/// An ordering on `T`, used in place of its `Ord` implementation.
+///
+/// Elements that compare equal must also hash equally, since the hash
+/// decides the shape of the tree.
+pub trait OrdBy<T> {
+    /// Compare `a` with `b`
+    fn cmp(a: &T, b: &T) -> Ordering;
+    /// Feed `t` into `state`
+    fn hash<H: Hasher>(t: &T, state: &mut H);
+}
+
+/// Orders `T` in reverse
+pub struct Reverse;
+
+impl<T> OrdBy<T> for Reverse
+    where T: Ord + Hash
+{
+    fn cmp(a: &T, b: &T) -> Ordering {
+        b.cmp(a)
+    }
+    fn hash<H: Hasher>(t: &T, state: &mut H) {
+        t.hash(state)
+    }
+}
+
+/// A `T` that is ordered by `O`, for use as set elements or map keys.
+pub struct By<T, O> {
+    t: T,
+    _o: PhantomData<O>,
+}
+
+impl<T, O> By<T, O>
+    where O: OrdBy<T>
+{
+    /// Wrap `t` to be ordered by `O`
+    pub fn new(t: T) -> Self {
+        By {
+            t: t,
+            _o: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the wrapped value
+    pub fn inner(&self) -> &T {
+        &self.t
+    }
+
+    /// Returns the wrapped value
+    pub fn into_inner(self) -> T {
+        self.t
+    }
+}
+
+impl<T, O> Clone for By<T, O>
+    where T: Clone
+{
+    fn clone(&self) -> Self {
+        By {
+            t: self.t.clone(),
+            _o: PhantomData,
+        }
+    }
+}
+
+impl<T, O> fmt::Debug for By<T, O>
+    where T: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.t.fmt(f)
+    }
+}
+
+impl<T, O> Hash for By<T, O>
+    where O: OrdBy<T>
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        O::hash(&self.t, state)
+    }
+}
+
+impl<T, O> PartialEq for By<T, O>
+    where O: OrdBy<T>
+{
+    fn eq(&self, other: &Self) -> bool {
+        O::cmp(&self.t, &other.t) == Ordering::Equal
+    }
+}
+
+impl<T, O> Eq for By<T, O> where O: OrdBy<T> {}
+
+impl<T, O> PartialOrd for By<T, O>
+    where O: OrdBy<T>
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, O> Ord for By<T, O>
+    where O: OrdBy<T>
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        O::cmp(&self.t, &other.t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    const LOTS: usize = 100_000;
+
+    use std::cmp::Ordering;
+    use std::hash::{Hash, Hasher};
+
+    use meta::max::Max;
+    use meta::checksum::CheckSum;
+
+    use collection::Collection;
+
+    use ops::set::SetOps;
+    use ops::map::MapOps;
+
+    use super::{OrdBy, By, Reverse};
+
+    collection!(Set<T> {
+        max: Max<T>,
+        checksum: CheckSum<u64>,
+    } where T: Ord + Hash);
+
+    mod map {
+        use meta::key::{Key, Keyed};
+
+        use collection::Collection;
+
+        collection!(Map<T> {
+            key: Key<T::Key>,
+        } where T: Keyed);
+    }
+
+    use self::map::Map;
+
+    struct NoCase;
+
+    impl OrdBy<&'static str> for NoCase {
+        fn cmp(a: &&'static str, b: &&'static str) -> Ordering {
+            a.to_lowercase().cmp(&b.to_lowercase())
+        }
+        fn hash<H: Hasher>(t: &&'static str, state: &mut H) {
+            t.to_lowercase().hash(state)
+        }
+    }
+
+    #[test]
+    fn reverse() {
+        let mut set = Set::new();
+
+        for i in 0..LOTS {
+            set.insert(By::<_, Reverse>::new(i));
+        }
+
+        let mut iter = set.iter();
+        for i in 0..LOTS {
+            assert_eq!(iter.next().map(|b| *b.inner()), Some(LOTS - i - 1));
+        }
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let mut map = Map::new();
+
+        map.insert(By::<_, NoCase>::new("Banana"), 1);
+        map.insert(By::new("apple"), 2);
+        map.insert(By::new("APPLE"), 3);
+
+        assert_eq!(map.get(By::new("aPPle")), Some(&3));
+        assert_eq!(map.get(By::new("BANANA")), Some(&1));
+
+        let keys: Vec<_> = map.keys().map(|k| *k.inner()).collect();
+        assert_eq!(keys, vec!["APPLE", "Banana"]);
+    }
+}
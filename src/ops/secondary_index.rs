@@ -0,0 +1,168 @@
+use Val;
+
+use collection::Collection;
+
+use meta::{Meta, SubMeta};
+use meta::key::Key;
+
+use ops::map::{KV, MapOps};
+use ops::multimap::MultiMapOps;
+
+/// A map along with an index on an attribute derived from its values,
+/// kept in sync as the map changes.
+///
+/// The index maps each attribute to the keys of the entries having it.
+pub struct SecondaryIndex<K, V, I, M, N, F>
+    where K: Val + Ord,
+          V: Clone,
+          I: Val + Ord,
+          M: Meta<KV<K, V>>,
+          N: Meta<KV<I, Vec<K>>>,
+          F: Fn(&V) -> I
+{
+    primary: Collection<KV<K, V>, M>,
+    index: Collection<KV<I, Vec<K>>, N>,
+    derive: F,
+}
+
+impl<K, V, I, M, N, F> SecondaryIndex<K, V, I, M, N, F>
+    where K: Val + Ord,
+          V: Clone,
+          I: Val + Ord,
+          M: Meta<KV<K, V>> + SubMeta<Key<K>>,
+          N: Meta<KV<I, Vec<K>>> + SubMeta<Key<I>>,
+          F: Fn(&V) -> I
+{
+    /// Constructs a new, empty map, indexed on the attribute `derive`
+    pub fn new(derive: F) -> Self {
+        SecondaryIndex {
+            primary: Collection::new(),
+            index: Collection::new(),
+            derive: derive,
+        }
+    }
+
+    /// Insert a value `V` at key `K`, updating the index
+    pub fn insert(&mut self, key: K, val: V) {
+        self.unindex(&key);
+        let attribute = (self.derive)(&val);
+        MapOps::insert(&mut self.primary, key.clone(), val);
+        MultiMapOps::insert(&mut self.index, attribute, key);
+    }
+
+    /// Remove value at key `K`, updating the index
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        self.unindex(&key);
+        MapOps::remove(&mut self.primary, key)
+    }
+
+    /// Change the value at key `K` with `f`, updating the index.
+    ///
+    /// Returns `false` if there was no value at `K`.
+    pub fn mutate<G>(&mut self, key: K, f: G) -> bool
+        where G: FnOnce(&mut V)
+    {
+        if !self.unindex(&key) {
+            return false;
+        }
+        let attribute = match self.primary.get_mut(key.clone()) {
+            Some(mut val) => {
+                f(&mut val);
+                (self.derive)(&val)
+            }
+            None => return false,
+        };
+        MultiMapOps::insert(&mut self.index, attribute, key);
+        true
+    }
+
+    /// Get a reference to the value at key `K`
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.primary.get(key)
+    }
+
+    /// Get the keys of all values with the given attribute, in the
+    /// order they were indexed
+    pub fn keys_by(&self, attribute: I) -> &[K] {
+        self.index.get_all(attribute)
+    }
+
+    /// Returns a reference to the underlying map
+    pub fn primary(&self) -> &Collection<KV<K, V>, M> {
+        &self.primary
+    }
+
+    // Removes the index entry for the value at `key`, if any
+    fn unindex(&mut self, key: &K) -> bool {
+        let attribute = match self.primary.get(key.clone()) {
+            Some(val) => (self.derive)(val),
+            None => return false,
+        };
+        self.index.remove_one(attribute, key);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    const LOTS: usize = 100_000;
+
+    use meta::key::{Key, Keyed};
+
+    use collection::Collection;
+
+    use ops::map::KV;
+
+    use super::SecondaryIndex;
+
+    collection!(Map<T> {
+        key: Key<T::Key>,
+    } where T: Keyed);
+
+    type Index<K, V, I, F> =
+        SecondaryIndex<K, V, I, col::CollectionMeta<KV<K, V>>,
+                       col::CollectionMeta<KV<I, Vec<K>>>, F>;
+
+    #[test]
+    fn insert_remove() {
+        let mut map: Index<_, _, _, _> = SecondaryIndex::new(|v: &usize| v % 10);
+
+        for i in 0..LOTS {
+            map.insert(i, i);
+        }
+
+        assert_eq!(map.keys_by(3).len(), LOTS / 10);
+        assert!(map.keys_by(3).iter().all(|k| k % 10 == 3));
+
+        // overwriting moves the key to another attribute
+        map.insert(3, 4);
+        assert_eq!(map.keys_by(3).len(), LOTS / 10 - 1);
+        assert_eq!(map.keys_by(4).len(), LOTS / 10 + 1);
+
+        for i in 0..LOTS {
+            if i % 10 == 4 {
+                assert_eq!(map.remove(i), Some(i));
+            }
+        }
+        assert_eq!(map.keys_by(4), &[3]);
+        assert_eq!(map.remove(LOTS), None);
+    }
+
+    #[test]
+    fn mutate() {
+        let mut map: Index<_, _, _, _> = SecondaryIndex::new(|v: &String| v.len());
+
+        map.insert("a", String::from("x"));
+        map.insert("b", String::from("yy"));
+
+        assert!(map.mutate("a", |v| v.push('x')));
+        assert!(!map.mutate("c", |v| v.push('x')));
+
+        assert_eq!(map.keys_by(1), &[] as &[&str]);
+        assert_eq!(map.keys_by(2), &["b", "a"]);
+        assert_eq!(map.get("a"), Some(&String::from("xx")));
+
+        let primary: &Map<_> = map.primary();
+        assert_eq!(primary.iter().count(), 2);
+    }
+}
@@ -1,15 +1,21 @@
 use Val;
 
+use std::mem;
+use std::iter::FromIterator;
 use std::hash::Hash;
+use std::collections::BTreeSet;
 
 use collection::Collection;
 
-use meta::{Meta, SubMeta};
+use meta::{Meta, SubMeta, Selection};
 use meta::max::Max;
+use meta::min::Min;
 use meta::checksum::CheckSum;
+use meta::cardinality::Cardinality;
 
 use tree::branch::{Branch, BranchResult};
 use tree::level::{Beginning, End};
+use tree::iterator::Iter;
 
 
 /// Set operations on a Collection
@@ -18,10 +24,27 @@ pub trait SetOps<T>
 {
     /// Insert element into set
     fn insert(&mut self, t: T);
+    /// Insert all elements of `iter` into set
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I);
+    /// Insert element into set, replacing and returning the existing
+    /// element equal to `t`, if any.
+    fn replace(&mut self, t: T) -> Option<T>;
     /// Remove element from set
     fn remove(&mut self, t: &T) -> Option<T>;
+    /// Remove and return the element stored in the set that is equal
+    /// to `t`, which may differ from `t` in parts not considered by `Ord`.
+    fn take(&mut self, t: &T) -> Option<T>;
     /// Is element a member of this set?
     fn member(&self, t: &T) -> bool;
+    /// Which of `items` are members of this set?
+    ///
+    /// The probes are sorted, and looked up in a single walk of the tree.
+    fn members(&self, items: &[T]) -> Vec<bool>;
+    /// Split the set in two, the first part containing all elements
+    /// less than `t`, and the second the rest.
+    fn split(&mut self, t: &T) -> (Self, Self);
+    /// Return a new Collection, that is the union of all `sets`
+    fn union_many(sets: &mut [Self]) -> Self;
 }
 
 /// Set operations on Checksummed sets
@@ -32,6 +55,41 @@ pub trait SetOpsCheckSum<T>
     fn union(&mut self, b: &mut Self) -> Self;
 }
 
+impl<T, M> Collection<T, M>
+    where T: Val + Ord,
+          M: Meta<T> + SubMeta<Max<T>>
+{
+    /// Returns an iterator over the set, starting at the first element
+    /// greater than or equal to `t`
+    pub fn iter_from<'a>(&'a self, t: &T) -> Iter<'a, T, M, Beginning> {
+        self.iter_from_using(Max::from_t(t))
+    }
+}
+
+impl<T, M> Collection<T, M>
+    where T: Val + Ord,
+          M: Meta<T> + SubMeta<Min<T>>
+{
+    /// Returns a reverse iterator over the set, starting at the last
+    /// element less than or equal to `t`
+    pub fn iter_rev_from<'a>(&'a self, t: &T) -> Iter<'a, T, M, End> {
+        self.iter_rev_from_using(Min::from_t(t))
+    }
+
+    /// Returns the last element less than `t`
+    pub fn last_before(&self, t: &T) -> Option<&T> {
+        self.iter_rev_from(t).find(|e| *e < t)
+    }
+}
+
+/// Set operations on sets with `Cardinality` metadata
+pub trait SetOpsCardinality<T>
+    where Self: SetOps<T>
+{
+    /// Count the elements `e` in the set where `from <= e < to`
+    fn count_range(&self, from: &T, to: &T) -> usize;
+}
+
 impl<T, M> SetOps<T> for Collection<T, M>
     where T: Val + Ord,
           M: Meta<T> + SubMeta<Max<T>>
@@ -47,6 +105,25 @@ impl<T, M> SetOps<T> for Collection<T, M>
         }
     }
 
+    fn members(&self, items: &[T]) -> Vec<bool> {
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        order.sort_by(|a, b| items[*a].cmp(&items[*b]));
+
+        let mut result = vec![false; items.len()];
+        let mut branch: Branch<_, _, Beginning> = Branch::new(self.root);
+
+        for i in order {
+            let mut search = Max::from_t(&items[i]);
+            match branch.find_full(&mut search, &self.stash) {
+                Selection::Hit => result[i] = true,
+                Selection::Between => (),
+                // Every remaining probe is past the end of the set
+                Selection::Miss => break,
+            }
+        }
+        result
+    }
+
     fn insert(&mut self, t: T) {
         let mut search = Max::from_t(&t);
         let branch = Branch::<_, _, Beginning>::new_full(self.root,
@@ -69,6 +146,39 @@ impl<T, M> SetOps<T> for Collection<T, M>
         }
     }
 
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for t in iter {
+            self.insert(t);
+        }
+    }
+
+    fn replace(&mut self, t: T) -> Option<T> {
+        let mut search = Max::from_t(&t);
+        let branch = Branch::<_, _, Beginning>::new_full(self.root,
+                                                         &mut search,
+                                                         &self.stash);
+        match branch {
+            BranchResult::Between(mut b) => {
+                b.insert(t, self.divisor, &mut self.stash);
+                self.root = b.root();
+                None
+            }
+            // Already there, swap it out and re-balance
+            BranchResult::Hit(b) => {
+                let mut context = self.mut_context(b);
+                Some(mem::replace(&mut *context, t))
+            }
+            // At the very end
+            BranchResult::Miss => {
+                let mut branch: Branch<_, _, End> = Branch::first(self.root,
+                                                                  &self.stash);
+                branch.insert(t, self.divisor, &mut self.stash);
+                self.root = branch.root();
+                None
+            }
+        }
+    }
+
     fn remove(&mut self, t: &T) -> Option<T> {
         let mut search = Max::from_t(t);
         let branch = Branch::<_, _, Beginning>::new_full(self.root,
@@ -84,6 +194,35 @@ impl<T, M> SetOps<T> for Collection<T, M>
             }
         }
     }
+
+    fn take(&mut self, t: &T) -> Option<T> {
+        // `remove` already hands back the stored element
+        self.remove(t)
+    }
+
+    fn split(&mut self, t: &T) -> (Self, Self) {
+        let (mut stash_a, mut stash_b) = self.stash.split(&mut self.root);
+
+        let mut search = Max::from_t(t);
+        let branch = Branch::<_, _, Beginning>::new_full(self.root,
+                                                         &mut search,
+                                                         &self.stash);
+        match branch {
+            BranchResult::Hit(b) |
+            BranchResult::Between(b) => {
+                let left = b.left(&mut stash_a);
+                let right = b.right(&mut stash_b);
+                (Collection::new_from(left.root(), stash_a),
+                 Collection::new_from(right.root(), stash_b))
+            }
+            // All elements are less than `t`
+            BranchResult::Miss => (self.clone_mut(), Collection::new()),
+        }
+    }
+
+    fn union_many(sets: &mut [Self]) -> Self {
+        Collection::union_many_using::<Max<T>>(sets)
+    }
 }
 
 impl<T, M> SetOpsCheckSum<T> for Collection<T, M>
@@ -97,6 +236,53 @@ impl<T, M> SetOpsCheckSum<T> for Collection<T, M>
     }
 }
 
+impl<T, M> SetOpsCardinality<T> for Collection<T, M>
+    where T: Val + Ord,
+          M: Meta<T> + SubMeta<Max<T>> + SubMeta<Cardinality<usize>>
+{
+    fn count_range(&self, from: &T, to: &T) -> usize {
+        let before_to = self.count_before(Max::from_t(to));
+        let before_from = self.count_before(Max::from_t(from));
+        before_to.saturating_sub(before_from)
+    }
+}
+
+impl<'a, T, M> From<&'a Collection<T, M>> for BTreeSet<T>
+    where T: Val + Ord,
+          M: Meta<T>
+{
+    fn from(collection: &'a Collection<T, M>) -> Self {
+        collection.iter().cloned().collect()
+    }
+}
+
+impl<T, M> From<BTreeSet<T>> for Collection<T, M>
+    where T: Val + Ord,
+          M: Meta<T> + SubMeta<Max<T>>
+{
+    /// Builds a set of the elements of `set`, which are already in order
+    fn from(set: BTreeSet<T>) -> Self {
+        Collection::from_sorted(set)
+    }
+}
+
+impl<T, M> FromIterator<T> for Collection<T, M>
+    where T: Val + Ord,
+          M: Meta<T> + SubMeta<Max<T>>
+{
+    /// Builds a set from elements in any order. Like with `insert`,
+    /// later elements equal to earlier ones are dropped.
+    fn from_iter<I>(iter: I) -> Self
+        where I: IntoIterator<Item = T>
+    {
+        let mut elements: Vec<T> = iter.into_iter().collect();
+        // Stable, so the first of equal elements comes first
+        elements.sort();
+        elements.dedup();
+        Collection::from_sorted(elements)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     extern crate rand;
@@ -105,27 +291,170 @@ mod tests {
     use super::SetOps;
     const LOTS: usize = 100_000;
 
-    use std::cmp::Ord;
-    use std::hash::Hash;
+    use std::cmp::{Ord, Ordering};
+    use std::collections::BTreeSet;
+    use std::hash::{Hash, Hasher};
 
     use meta::max::Max;
-    use meta::checksum::CheckSum;
+    use meta::checksum::{self, CheckSum};
 
     use collection::Collection;
 
     use super::SetOpsCheckSum;
+    use super::SetOpsCardinality;
 
     collection!(Set<T> {
         max: Max<T>,
         checksum: CheckSum<u64>,
     } where T: Ord + Hash);
 
+    mod counted {
+        use std::hash::Hash;
+
+        use meta::max::Max;
+        use meta::cardinality::Cardinality;
+
+        use collection::Collection;
+
+        collection!(CountedSet<T> {
+            max: Max<T>,
+            cardinality: Cardinality<usize>,
+        } where T: Ord + Hash);
+    }
+
+    use self::counted::CountedSet;
+
+    /// Ordered only by the first field
+    #[derive(Clone, Debug)]
+    struct Tagged(usize, usize);
+
+    impl PartialEq for Tagged {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Hash for Tagged {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.0.hash(state)
+        }
+    }
+
+    impl Eq for Tagged {}
+
+    impl PartialOrd for Tagged {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Tagged {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
     #[test]
     fn insert_one() {
         let mut set = Set::new();
         set.insert(42);
     }
 
+    #[test]
+    fn std_set() {
+        let std: BTreeSet<usize> = (0..LOTS).map(|i| i * 7 % LOTS).collect();
+
+        let set = Set::from(std.clone());
+        let mut reference = Set::new();
+        for i in 0..LOTS {
+            reference.insert(i * 7 % LOTS);
+        }
+        assert!(set == reference);
+        assert_eq!(BTreeSet::from(&set), std);
+        assert_eq!(BTreeSet::from(&Set::<usize>::new()), BTreeSet::new());
+    }
+
+    #[test]
+    fn from_iter_extend() {
+        let set: Set<usize> = (0..LOTS).rev().chain(0..LOTS / 2).collect();
+        let mut reference = Set::new();
+        for i in 0..LOTS {
+            reference.insert(i);
+        }
+        assert!(set == reference);
+
+        let mut extended = Set::new();
+        extended.extend((0..LOTS).filter(|i| i % 2 == 1));
+        extended.extend((0..LOTS).rev().filter(|i| i % 2 == 0));
+        extended.extend(None);
+        assert!(extended == reference);
+
+        let empty: Set<usize> = None.into_iter().collect();
+        assert!(empty.iter().next().is_none());
+    }
+
+    #[test]
+    fn replace() {
+        let mut set = Set::new();
+        let mut reference = Set::new();
+
+        for i in 0..LOTS {
+            assert_eq!(set.replace(Tagged(i, 0)), None);
+            reference.insert(Tagged(i, 1));
+        }
+
+        for i in 0..LOTS {
+            let old = set.replace(Tagged(i, 1)).expect("present");
+            assert_eq!(old.1, 0);
+        }
+
+        for (i, t) in set.iter().enumerate() {
+            assert_eq!(t.0, i);
+            assert_eq!(t.1, 1);
+        }
+
+        assert!(set == reference);
+    }
+
+    #[test]
+    fn replace_last() {
+        let mut set = Set::new();
+
+        for i in 0..100 {
+            set.insert(Tagged(i, 0));
+        }
+
+        // Only the last element is replaced, every time
+        for j in 0..1000 {
+            assert_eq!(set.replace(Tagged(99, j + 1)).map(|t| t.1), Some(j));
+            assert_eq!(set.iter().count(), 100);
+        }
+
+        let mut reference = Set::new();
+        for i in 0..99 {
+            reference.insert(Tagged(i, 0));
+        }
+        reference.insert(Tagged(99, 1000));
+        assert!(set == reference);
+    }
+
+    #[test]
+    fn take() {
+        let mut set = Set::new();
+
+        for i in 0..LOTS {
+            set.insert(Tagged(i, i + 1));
+        }
+
+        for i in 0..LOTS {
+            let taken = set.take(&Tagged(i, 0)).expect("present");
+            assert_eq!(taken.1, i + 1);
+            assert_eq!(set.take(&Tagged(i, 0)).map(|t| t.1), None);
+        }
+
+        assert!(set == Set::new());
+    }
+
     #[test]
     fn member() {
         let mut set = Set::new();
@@ -143,6 +472,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn members() {
+        let mut set = Set::new();
+
+        for i in 0..LOTS / 2 {
+            set.insert(i * 2);
+        }
+
+        let mut probes = vec![];
+        for i in 0..LOTS + 10 {
+            probes.push(i);
+            if i % 7 == 0 {
+                probes.push(i);
+            }
+        }
+        rand::thread_rng().shuffle(&mut probes);
+
+        let result = set.members(&probes);
+        assert_eq!(result.len(), probes.len());
+
+        for i in 0..probes.len() {
+            assert_eq!(result[i], probes[i] % 2 == 0 && probes[i] < LOTS);
+        }
+
+        assert_eq!(Set::new().members(&[1, 2, 3]), vec![false, false, false]);
+    }
+
+    #[test]
+    fn count_range() {
+        let mut set = CountedSet::new();
+
+        assert_eq!(set.count_range(&0, &10), 0);
+
+        for i in 0..LOTS / 2 {
+            set.insert(i * 2);
+        }
+
+        assert_eq!(set.count_range(&0, &LOTS), LOTS / 2);
+        assert_eq!(set.count_range(&0, &(LOTS * 2)), LOTS / 2);
+        assert_eq!(set.count_range(&10, &0), 0);
+
+        for i in 0..100 {
+            let from = (LOTS / 100) * i;
+            let to = from + i * 7 + 1;
+            let reference = (from..to).filter(|e| e % 2 == 0 && *e < LOTS).count();
+            assert_eq!(set.count_range(&from, &to), reference);
+        }
+    }
+
     #[test]
     fn set_insert() {
 
@@ -172,6 +550,32 @@ mod tests {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn iter_from() {
+        let mut set = Set::new();
+
+        for i in 0..LOTS / 2 {
+            set.insert(i * 2);
+        }
+
+        for i in 0..100 {
+            let from = (LOTS / 100) * i + i % 2;
+            let mut iter = set.iter_from(&from);
+            let mut expected = from + from % 2;
+            for _ in 0..10 {
+                assert_eq!(iter.next(), Some(&expected));
+                expected += 2;
+            }
+        }
+
+        let mut iter = set.iter_from(&(LOTS - 2));
+        assert_eq!(iter.next(), Some(&(LOTS - 2)));
+        assert_eq!(iter.next(), None);
+
+        assert_eq!(set.iter_from(&LOTS).next(), None);
+        assert_eq!(Set::new().iter_from(&0).next(), None);
+    }
+
     #[test]
     fn set_remove() {
         debug_assert!(LOTS % 2 == 0);
@@ -371,6 +775,143 @@ mod tests {
         assert!(set_a == set_b);
     }
 
+    #[test]
+    fn split() {
+        let mut set = Set::new();
+
+        for i in 0..LOTS / 2 {
+            set.insert(i * 2);
+        }
+
+        let mut split_points = vec![0, 1, LOTS - 2, LOTS - 1, LOTS];
+        for i in 0..100 {
+            split_points.push((LOTS / 100) * i + 1);
+        }
+
+        for i in split_points {
+            let (a, b) = set.split(&i);
+
+            let mut iter_a = a.iter();
+            let mut iter_b = b.iter();
+
+            for o in 0..LOTS / 2 {
+                if o * 2 < i {
+                    assert_eq!(iter_a.next(), Some(&(o * 2)))
+                } else {
+                    assert_eq!(iter_b.next(), Some(&(o * 2)))
+                }
+            }
+            assert_eq!(iter_a.next(), None);
+            assert_eq!(iter_b.next(), None);
+        }
+
+        let (a, b) = Set::new().split(&0);
+        assert!(a == Set::new());
+        assert!(b == Set::new());
+    }
+
+    #[test]
+    fn drain() {
+        let mut set = Set::new();
+        let empty = Set::new();
+
+        for i in 0..LOTS {
+            set.insert(LOTS - i - 1);
+        }
+
+        let mut count = 0;
+        for t in set.drain() {
+            assert_eq!(t, count);
+            count += 1;
+        }
+        assert_eq!(count, LOTS);
+
+        assert!(set == empty);
+        assert_eq!(set.iter().next(), None);
+    }
+
+    #[test]
+    fn into_iter() {
+        let mut set = Set::new();
+
+        for i in 0..LOTS {
+            set.insert(LOTS - i - 1);
+        }
+
+        // Shares all its nodes with `set`, until one of them changes
+        let mut shared = set.clone_mut();
+        shared.insert(LOTS);
+
+        let mut count = 0;
+        for t in set {
+            assert_eq!(t, count);
+            count += 1;
+        }
+        assert_eq!(count, LOTS);
+
+        assert!(shared.into_iter().eq(0..LOTS + 1));
+        assert_eq!(Set::<usize>::new().into_iter().next(), None);
+    }
+
+    #[test]
+    fn ordering() {
+        let mut sets = vec![];
+        for i in 0..100 {
+            sets.push((0..LOTS / 100).map(|j| j * (i % 7 + 1)).collect());
+        }
+        sets.push(Set::new());
+        sets.push(Set::new());
+
+        let mut sorted: Vec<Set<usize>> = sets.iter_mut()
+            .map(|s| s.clone_mut())
+            .collect();
+        sorted.sort();
+
+        let mut reference: Vec<Vec<usize>> =
+            sets.iter().map(|s| s.iter().cloned().collect()).collect();
+        reference.sort();
+
+        for (set, vec) in sorted.iter().zip(reference.iter()) {
+            assert!(set.iter().eq(vec.iter()));
+        }
+
+        let mut a = Set::new();
+        let mut b = Set::new();
+        a.insert(1);
+        b.insert(1);
+        b.insert(2);
+        assert!(a < b);
+        assert!(b > a);
+        let c = a.clone_mut();
+        assert_eq!(a.cmp(&c), Ordering::Equal);
+        assert!(Set::new() < a);
+
+        // Usable as keys of ordered collections
+        let mut of_sets = BTreeSet::new();
+        for set in sets {
+            of_sets.insert(set);
+        }
+        assert_eq!(of_sets.len(), 8);
+    }
+
+    #[test]
+    fn checksum_combine() {
+        // Both 3 with the earlier merge `(a ^ b) + a`, which ignored the
+        // bits of `a` set in `b`
+        assert!(checksum::combine(2, 3) != checksum::combine(1, 3));
+        assert!(checksum::combine(1, 2) != checksum::combine(2, 1));
+
+        let mut a = Set::new();
+        let mut b = Set::new();
+        for i in 0..LOTS {
+            a.insert(i);
+            b.insert(i);
+        }
+        b.remove(&(LOTS / 2));
+        b.insert(LOTS);
+        assert!(a != b);
+    }
+
     #[test]
     fn union() {
         let mut a = Set::new();
@@ -390,6 +931,34 @@ mod tests {
         assert!(r == u)
     }
 
+    #[test]
+    fn union_many() {
+        const SHARDS: usize = 10;
+
+        let mut sets: Vec<_> = (0..SHARDS).map(|_| Set::new()).collect();
+        let mut r = Set::new();
+
+        for i in 0..LOTS {
+            sets[i % SHARDS].insert(i);
+            // some overlap between neighbouring shards
+            if i % 13 == 0 {
+                sets[(i + 1) % SHARDS].insert(i);
+            }
+            r.insert(i);
+        }
+
+        let u = Set::union_many(&mut sets);
+        assert!(r == u);
+
+        let mut iter = u.iter();
+        for i in 0..LOTS {
+            assert_eq!(iter.next(), Some(&i));
+        }
+        assert_eq!(iter.next(), None);
+
+        assert!(Set::<usize>::union_many(&mut []) == Set::new());
+    }
+
     #[test]
     fn union_overlapping() {
         let mut a = Set::new();
@@ -411,4 +980,262 @@ mod tests {
         let u = a.union(&mut b);
         assert!(r == u)
     }
+
+    #[test]
+    fn is_empty() {
+        let mut set = Set::new();
+
+        assert!(set.is_empty());
+
+        for i in 0..LOTS {
+            set.insert(i);
+            assert!(!set.is_empty());
+        }
+        let copy = set.clone_mut();
+
+        for i in 0..LOTS {
+            assert!(!set.is_empty());
+            set.remove(&i);
+        }
+        assert!(set.is_empty());
+        assert!(!copy.is_empty());
+
+        let (a, b) = Set::from_sorted(0..10).split(&10);
+        assert!(!a.is_empty());
+        assert!(b.is_empty());
+    }
+
+    #[test]
+    fn clear() {
+        let mut set = Set::new();
+
+        for i in 0..LOTS {
+            set.insert(i);
+        }
+        let copy = set.clone_mut();
+        set.snapshot("full");
+
+        set.clear();
+        assert!(set.is_empty());
+        assert!(set == Set::new());
+        assert_eq!(set.stash.len(), 1);
+        assert!(copy.iter().cloned().eq(0..LOTS));
+
+        set.insert(1);
+        assert!(set.member(&1));
+        assert!(!set.member(&2));
+
+        assert!(set.restore_snapshot("full"));
+        assert!(set == copy);
+    }
+
+    #[test]
+    fn len() {
+        let mut set = CountedSet::new();
+
+        assert_eq!(set.len(), 0);
+        assert!(set.is_empty());
+
+        for i in 0..LOTS {
+            set.insert(i);
+            set.insert(i / 2);
+        }
+        assert_eq!(set.len(), LOTS);
+
+        let (a, b) = set.split(&(LOTS / 4));
+        assert_eq!(a.len(), LOTS / 4);
+        assert_eq!(b.len(), LOTS - LOTS / 4);
+    }
+
+    mod bounded {
+        use std::hash::Hash;
+
+        use meta::Meta;
+        use meta::bounds::Bounds;
+        use meta::max::Max;
+        use meta::min::Min;
+
+        use collection::Collection;
+
+        use super::super::SetOps;
+        use super::LOTS;
+
+        collection!(MinMaxSet<T> {
+            max: Max<T>,
+            min: Min<T>,
+            bounds: Bounds<T>,
+        } where T: Ord + Hash);
+
+        #[test]
+        fn iter_rev_from_min() {
+            let mut set = MinMaxSet::new();
+
+            for i in 0..LOTS {
+                set.insert(i * 2);
+            }
+
+            for i in 0..LOTS {
+                let from = i * 2 + 1;
+                let mut iter = set.iter_rev_from_using(Min::from_t(&from));
+                assert_eq!(iter.next(), Some(&(i * 2)));
+                let mut iter = set.iter_rev_from_using(Min::from_t(&(i * 2)));
+                assert_eq!(iter.next(), Some(&(i * 2)));
+            }
+
+            let iter = set.iter_rev_from_using(Min::from_t(&LOTS));
+            let expected = (0..LOTS / 2 + 1).rev().map(|i| i * 2);
+            assert!(iter.cloned().eq(expected));
+            assert_eq!(set.iter_rev_from_using(Min::from_t(&0)).count(), 1);
+        }
+
+        #[test]
+        fn last_before() {
+            let mut set = MinMaxSet::new();
+
+            for i in 1..LOTS {
+                set.insert(i * 2);
+            }
+
+            for i in 1..LOTS {
+                assert_eq!(set.last_before(&(i * 2 + 1)), Some(&(i * 2)));
+                assert_eq!(set.last_before(&(i * 2 + 2)), Some(&(i * 2)));
+                assert_eq!(set.iter_rev_from(&(i * 2)).next(), Some(&(i * 2)));
+            }
+            assert_eq!(set.last_before(&2), None);
+            assert_eq!(set.last_before(&0), None);
+            assert_eq!(set.iter_rev_from(&1).next(), None);
+        }
+
+        #[test]
+        fn iter_bounds() {
+            let mut set = MinMaxSet::new();
+
+            for i in 0..LOTS {
+                set.insert(i * 3);
+            }
+
+            for i in 0..LOTS / 10 {
+                let (lo, hi) = (i * 29, i * 29 + i);
+                let found: Vec<_> = set.iter_from_using(Bounds::new(&lo, &hi))
+                    .take_while(|t| **t <= hi)
+                    .cloned()
+                    .collect();
+                let expected: Vec<_> =
+                    (lo..hi + 1).filter(|t| t % 3 == 0).collect();
+                assert_eq!(found, expected);
+            }
+
+            let past = Bounds::new(&(LOTS * 3), &(LOTS * 4));
+            assert_eq!(set.iter_from_using(past).next(), None);
+        }
+    }
+
+    mod sketched {
+        use std::hash::Hash;
+
+        use meta::max::Max;
+        use meta::checksum::CheckSum;
+        use meta::distinct::Distinct;
+
+        use collection::Collection;
+
+        use super::super::{SetOps, SetOpsCheckSum};
+        use super::LOTS;
+
+        collection!(SketchedSet<T> {
+            max: Max<T>,
+            checksum: CheckSum<u64>,
+            distinct: Distinct<T>,
+        } where T: Ord + Hash);
+
+        fn assert_close(estimate: usize, exact: usize) {
+            let error = (estimate as f64 - exact as f64).abs() / exact as f64;
+            assert!(error < 0.2, "{} is not close to {}", estimate, exact);
+        }
+
+        #[test]
+        fn distinct() {
+            let mut a = SketchedSet::new();
+            let mut b = SketchedSet::new();
+
+            assert_eq!(a.distinct(), 0);
+
+            for i in 0..LOTS {
+                a.insert(i);
+                b.insert(i + LOTS / 2);
+            }
+
+            assert_close(a.distinct(), LOTS);
+            assert_close(a.union(&mut b).distinct(), LOTS / 2 * 3);
+
+            let mut small = SketchedSet::new();
+            for i in 0..10 {
+                small.insert(i);
+            }
+            assert_close(small.distinct(), 10);
+        }
+    }
+
+    mod seeded {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        use meta::max::Max;
+        use meta::checksum::CheckSumWith;
+
+        use collection::Collection;
+
+        use super::super::SetOps;
+        use super::LOTS;
+
+        // A hasher other than SeaHasher, always seeded the same
+        #[derive(Clone)]
+        pub struct SeededHasher(DefaultHasher);
+
+        impl Default for SeededHasher {
+            fn default() -> Self {
+                let mut hasher = DefaultHasher::new();
+                hasher.write_u64(0x5ec2e7);
+                SeededHasher(hasher)
+            }
+        }
+
+        impl Hasher for SeededHasher {
+            fn write(&mut self, bytes: &[u8]) {
+                self.0.write(bytes)
+            }
+            fn finish(&self) -> u64 {
+                self.0.finish()
+            }
+        }
+
+        collection!(SeededSet<T> {
+            max: Max<T>,
+            checksum: CheckSumWith<SeededHasher>,
+        } where T: Ord + Hash);
+
+        #[test]
+        fn eq_union_using() {
+            let mut a = SeededSet::new();
+            let mut b = SeededSet::new();
+            let mut all = SeededSet::new();
+
+            for i in 0..LOTS {
+                if i % 2 == 0 {
+                    a.insert(i);
+                } else {
+                    b.insert(i);
+                }
+                all.insert(i);
+            }
+
+            type Sum = CheckSumWith<SeededHasher>;
+
+            assert!(!a.eq_using::<Sum>(&b));
+
+            let union = a.union_using::<Max<usize>, Sum>(&mut b);
+            assert!(union.eq_using::<Sum>(&all));
+            assert!(union.iter().eq(all.iter()));
+        }
+    }
 }
@@ -0,0 +1,158 @@
+use std::cmp::Ordering;
+use std::iter::Peekable;
+
+use Val;
+
+use collection::Collection;
+
+use meta::{Meta, SubMeta};
+use meta::max::Max;
+
+use ops::set::SetOps;
+
+use tree::iterator::Iter;
+use tree::level::Beginning;
+
+/// A read-only view of the union of two sets, that does not construct
+/// a new tree.
+///
+/// Useful when the union is only to be scanned or queried a few times.
+pub struct UnionView<'a, T, M>
+    where T: 'a + Val,
+          M: 'a + Meta<T>
+{
+    a: &'a Collection<T, M>,
+    b: &'a Collection<T, M>,
+}
+
+/// An iterator over the union of two sets, in order.
+pub struct UnionIter<'a, T, M>
+    where T: 'a + Val,
+          M: 'a + Meta<T>
+{
+    a: Peekable<Iter<'a, T, M, Beginning>>,
+    b: Peekable<Iter<'a, T, M, Beginning>>,
+}
+
+impl<'a, T, M> UnionView<'a, T, M>
+    where T: 'a + Val + Ord,
+          M: 'a + Meta<T>
+{
+    /// Constructs a view of the union of `a` and `b`
+    pub fn new(a: &'a Collection<T, M>, b: &'a Collection<T, M>) -> Self {
+        UnionView { a: a, b: b }
+    }
+
+    /// Returns an iterator over the union.
+    ///
+    /// Like `union`, elements present in both sets are picked from `b`.
+    pub fn iter(&self) -> UnionIter<'a, T, M> {
+        UnionIter {
+            a: self.a.iter().peekable(),
+            b: self.b.iter().peekable(),
+        }
+    }
+}
+
+impl<'a, T, M> UnionView<'a, T, M>
+    where T: 'a + Val + Ord,
+          M: 'a + Meta<T> + SubMeta<Max<T>>
+{
+    /// Is element a member of the union?
+    pub fn member(&self, t: &T) -> bool {
+        self.a.member(t) || self.b.member(t)
+    }
+}
+
+impl<'a, T, M> Iterator for UnionIter<'a, T, M>
+    where T: 'a + Val + Ord,
+          M: 'a + Meta<T>
+{
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let order = match (self.a.peek(), self.b.peek()) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => Ordering::Less,
+            (None, _) => Ordering::Greater,
+        };
+        match order {
+            Ordering::Less => self.a.next(),
+            Ordering::Greater => self.b.next(),
+            Ordering::Equal => {
+                self.a.next();
+                self.b.next()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    const LOTS: usize = 100_000;
+
+    use std::hash::Hash;
+
+    use meta::max::Max;
+    use meta::checksum::CheckSum;
+
+    use collection::Collection;
+
+    use ops::set::SetOps;
+
+    use super::UnionView;
+
+    collection!(Set<T> {
+        max: Max<T>,
+        checksum: CheckSum<u64>,
+    } where T: Ord + Hash);
+
+    #[test]
+    fn iter() {
+        let mut a = Set::new();
+        let mut b = Set::new();
+
+        for i in 0..LOTS {
+            if i % 3 != 0 {
+                a.insert(i);
+            }
+            if i % 2 == 0 {
+                b.insert(i);
+            }
+        }
+
+        let view = UnionView::new(&a, &b);
+        let mut iter = view.iter();
+
+        for i in 0..LOTS {
+            if i % 6 != 3 {
+                assert_eq!(iter.next(), Some(&i));
+            }
+        }
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn member() {
+        let mut a = Set::new();
+        let mut b = Set::new();
+
+        for i in 0..LOTS / 2 {
+            a.insert(i * 2);
+            b.insert(i * 3);
+        }
+
+        let view = UnionView::new(&a, &b);
+
+        for i in 0..LOTS {
+            let expected = (i % 2 == 0 && i < LOTS) ||
+                           (i % 3 == 0 && i < (LOTS / 2) * 3);
+            assert_eq!(view.member(&i), expected);
+        }
+
+        let empty = Set::<usize>::new();
+        let view = UnionView::new(&empty, &empty);
+        assert!(!view.member(&0));
+        assert_eq!(view.iter().next(), None);
+    }
+}
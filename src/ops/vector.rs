@@ -1,12 +1,92 @@
+use std::cmp;
+use std::error::Error;
+use std::fmt;
+use std::iter::Take;
+use std::mem;
+use std::ops::{Bound, Index, Range, RangeBounds};
+
 use collection::{Collection, MutContext};
 
 use meta::{Meta, SubMeta};
 use meta::cardinality::Cardinality;
+use meta::max::Max;
 
 use Val;
 
 use tree::branch::{Branch, BranchResult};
 use tree::level::{Beginning, End};
+use tree::iterator::{Iter, Drain};
+
+/// The error returned when inserting past the end of a vector.
+///
+/// Holds on to the element that could not be inserted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutOfBounds<T> {
+    index: usize,
+    t: T,
+}
+
+impl<T> OutOfBounds<T> {
+    /// Returns the index that was out of bounds
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    /// Returns the element that was not inserted
+    pub fn into_inner(self) -> T {
+        self.t
+    }
+}
+
+impl<T> fmt::Display for OutOfBounds<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Insert past length of collection, at index {}", self.index)
+    }
+}
+
+impl<T> Error for OutOfBounds<T>
+    where T: fmt::Debug
+{
+    fn description(&self) -> &str {
+        "Insert past length of collection"
+    }
+}
+
+/// An iterator over a vector yielding elements along with their index,
+/// that can seek to any index.
+pub struct IndexedIter<'a, T, M>
+    where T: 'a + Val,
+          M: 'a + Meta<T>
+{
+    collection: &'a Collection<T, M>,
+    iter: Iter<'a, T, M, Beginning>,
+    index: usize,
+}
+
+impl<'a, T, M> IndexedIter<'a, T, M>
+    where T: 'a + Val,
+          M: 'a + Meta<T> + SubMeta<Cardinality<usize>>
+{
+    /// Continue iteration from index i
+    pub fn seek(&mut self, i: usize) {
+        self.iter = self.collection.iter_from_using(Cardinality::new(&i));
+        self.index = i;
+    }
+}
+
+impl<'a, T, M> Iterator for IndexedIter<'a, T, M>
+    where T: 'a + Val,
+          M: 'a + Meta<T>
+{
+    type Item = (usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|t| {
+            let i = self.index;
+            self.index += 1;
+            (i, t)
+        })
+    }
+}
 
 /// Vector Operations on a Collection
 pub trait VectorOps<T, M>
@@ -15,23 +95,191 @@ pub trait VectorOps<T, M>
           M: Meta<T>
 {
     /// Insert element at index i
+    ///
+    /// Panics if `i` is greater than the length of the vector.
     fn insert(&mut self, i: usize, t: T);
+    /// Insert element at index i, or return an error if `i` is greater
+    /// than the length of the vector.
+    fn try_insert(&mut self, i: usize, t: T) -> Result<(), OutOfBounds<T>>;
     /// Remove element from index i
     fn remove(&mut self, i: usize) -> Option<T>;
     /// Get a reference to element at index i
     fn get(&self, i: usize) -> Option<&T>;
     /// Get a mutable reference to element at index i
     fn get_mut(&mut self, i: usize) -> Option<MutContext<T, M, Beginning>>;
+    /// Replace the element at index i, returning the old element
+    fn set(&mut self, i: usize, t: T) -> Option<T>;
+    /// Swap the elements at indices i and j.
+    ///
+    /// Returns `false`, leaving the vector unchanged, if either index is
+    /// out of bounds.
+    fn swap(&mut self, i: usize, j: usize) -> bool;
     /// Push element to end of vector
     fn push(&mut self, t: T);
+    /// Push all elements of `iter` to the end of the vector
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I);
     /// Pop from the end of the vector
     fn pop(&mut self) -> Option<T>;
+    /// Returns a new vector with element pushed to the end, leaving self
+    /// unchanged.
+    ///
+    /// The new vector shares the nodes of self, except for the ones
+    /// changed since the last `clone_mut`, which are copied.
+    fn pushed(&self, t: T) -> Self;
+    /// Returns a new vector with element inserted at index i, leaving
+    /// self unchanged
+    fn inserted(&self, i: usize, t: T) -> Self;
+    /// Returns a new vector with the element at index i removed, along
+    /// with the removed element, leaving self unchanged
+    fn removed(&self, i: usize) -> (Self, Option<T>);
+    /// Get a reference to the last element of the vector
+    fn last(&self) -> Option<&T>;
+    /// Returns the length of the vector
+    fn len(&self) -> usize;
+    /// Returns `true` if the vector has no elements
+    fn is_empty(&self) -> bool;
     /// Split the vector in two at index i
     fn split(&mut self, i: usize) -> (Self, Self);
+    /// Shorten the vector to its first n elements
+    fn truncate(&mut self, n: usize);
+    /// Split the vector at index n, returning the elements from n onwards
+    /// and keeping the ones before
+    fn split_off(&mut self, n: usize) -> Self;
+    /// Removes consecutive repeated elements
+    fn dedup(&mut self)
+        where T: PartialEq
+    {
+        self.dedup_by_key(|t| t.clone())
+    }
+    /// Removes consecutive elements that map to the same key
+    fn dedup_by_key<K, F>(&mut self, f: F)
+        where K: PartialEq,
+              F: FnMut(&T) -> K;
+    /// Returns an ordered set of the elements of the vector, with
+    /// duplicates removed
+    fn sorted<N>(&self) -> Collection<T, N>
+        where T: Ord,
+              N: Meta<T> + SubMeta<Max<T>>;
+    /// Returns a new vector with the elements in reverse order
+    fn reverse(&self) -> Self;
+    /// Rotate the vector in place so that the element at index n becomes
+    /// the first element.
+    ///
+    /// Panics if n is greater than the length of the vector.
+    fn rotate_left(&mut self, n: usize);
+    /// Rotate the vector in place so that the element at index
+    /// `len - n` becomes the first element.
+    ///
+    /// Panics if n is greater than the length of the vector.
+    fn rotate_right(&mut self, n: usize);
     /// Concatenate two vectors
     fn concat(&mut self, b: &mut Self) -> Self;
     /// Splice in a vector at index i
     fn splice(&mut self, i: usize, from: &mut Self) -> Self;
+    /// Splice in a vector at index i, consuming it
+    fn splice_into(&mut self, i: usize, from: Self);
+    /// Insert all `items` at index i, in order
+    ///
+    /// Panics if `i` is greater than the length of the vector.
+    fn insert_all(&mut self, i: usize, items: Vec<T>);
+    /// Removes the elements in `range`, returning them as an iterator
+    /// of owned values.
+    ///
+    /// Unlike `drain`, which empties the whole collection, this accepts
+    /// any range, such as `a..b`, `a..` or `..=b`.
+    ///
+    /// Panics, as `Vec::drain` does, if the range starts after it ends,
+    /// or ends past the length of the vector.
+    fn drain_range<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<T, M>;
+    /// Returns an iterator over the elements in `range`
+    fn iter_range<'a>(&'a self,
+                      range: Range<usize>)
+                      -> Take<Iter<'a, T, M, Beginning>>;
+    /// Returns a view of the elements in `range`.
+    ///
+    /// The range is clamped to the length of the vector.
+    fn slice<'a>(&'a self, range: Range<usize>) -> VectorSlice<'a, T, M>;
+    /// Returns an iterator over the vector in chunks of n elements
+    ///
+    /// Panics if n is 0.
+    fn chunks<'a>(&'a self, n: usize) -> Chunks<'a, T, M>;
+    /// Returns an iterator over the elements and their indices
+    fn iter_indexed<'a>(&'a self) -> IndexedIter<'a, T, M>;
+}
+
+/// An iterator over a vector in chunks of `n` elements.
+///
+/// The last chunk may be shorter.
+pub struct Chunks<'a, T, M>
+    where T: 'a + Val,
+          M: 'a + Meta<T>
+{
+    iter: Iter<'a, T, M, Beginning>,
+    n: usize,
+}
+
+impl<'a, T, M> Iterator for Chunks<'a, T, M>
+    where T: 'a + Val,
+          M: 'a + Meta<T>
+{
+    type Item = Vec<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk: Vec<_> = self.iter.by_ref().take(self.n).collect();
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(chunk)
+        }
+    }
+}
+
+/// A read-only view of a range of a vector, that does not construct
+/// a new tree.
+pub struct VectorSlice<'a, T, M>
+    where T: 'a + Val,
+          M: 'a + Meta<T>
+{
+    collection: &'a Collection<T, M>,
+    start: usize,
+    end: usize,
+}
+
+impl<'a, T, M> VectorSlice<'a, T, M>
+    where T: 'a + Val,
+          M: 'a + Meta<T> + SubMeta<Cardinality<usize>>
+{
+    /// Get a reference to element at index i of the slice
+    pub fn get(&self, i: usize) -> Option<&'a T> {
+        if i < self.len() {
+            self.collection.get(self.start + i)
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over the slice
+    pub fn iter(&self) -> Take<Iter<'a, T, M, Beginning>> {
+        self.collection.iter_range(self.start..self.end)
+    }
+
+    /// Returns the length of the slice
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns `true` if the slice has no elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Operations on sorted vectors with `Max` metadata
+pub trait VectorOpsMax<T> {
+    /// Search the sorted vector for element, returning `Ok` with its
+    /// index if found, or `Err` with the index where it could be inserted
+    /// keeping the vector sorted.
+    fn binary_search(&self, t: &T) -> Result<usize, usize>;
 }
 
 impl<T, M> VectorOps<T, M> for Collection<T, M>
@@ -63,6 +311,12 @@ impl<T, M> VectorOps<T, M> for Collection<T, M>
     }
 
     fn insert(&mut self, i: usize, t: T) {
+        if let Err(e) = self.try_insert(i, t) {
+            panic!("{}", e);
+        }
+    }
+
+    fn try_insert(&mut self, i: usize, t: T) -> Result<(), OutOfBounds<T>> {
         let mut key = Cardinality::new(&i);
         let res: BranchResult<_, _, Beginning> =
             Branch::new_full(self.root, &mut key, &self.stash);
@@ -73,7 +327,7 @@ impl<T, M> VectorOps<T, M> for Collection<T, M>
                     branch.insert(t, self.divisor, &mut self.stash);
                     self.root = branch.root();
                 } else {
-                    panic!("Insert past length of collection");
+                    return Err(OutOfBounds { index: i, t: t });
                 }
             }
             BranchResult::Hit(mut branch) => {
@@ -88,10 +342,11 @@ impl<T, M> VectorOps<T, M> for Collection<T, M>
                     branch.insert(t, self.divisor, &mut self.stash);
                     self.root = branch.root();
                 } else {
-                    panic!("Insert past length of collection");
+                    return Err(OutOfBounds { index: i, t: t });
                 }
             }
         }
+        Ok(())
     }
 
     fn remove(&mut self, i: usize) -> Option<T> {
@@ -110,6 +365,45 @@ impl<T, M> VectorOps<T, M> for Collection<T, M>
         }
     }
 
+    fn set(&mut self, i: usize, t: T) -> Option<T> {
+        let mut state = Cardinality::new(&i);
+
+        let res: BranchResult<_, _, Beginning> =
+            Branch::new_full(self.root, &mut state, &self.stash);
+
+        match res {
+            BranchResult::Hit(mut branch) => {
+                // The weight of an element depends on its value, so the
+                // tree might still need re-balancing.
+                let new_weight = t.weight() / self.divisor;
+                let old = branch.leaf_mut(&mut self.stash)
+                    .map(|leaf| mem::replace(leaf, t));
+                let old_weight =
+                    old.as_ref().map(|o| o.weight()).unwrap_or(0) /
+                    self.divisor;
+                branch.rebalance(old_weight, new_weight, &mut self.stash);
+                self.root = branch.root();
+                old
+            }
+            _ => None,
+        }
+    }
+
+    fn swap(&mut self, i: usize, j: usize) -> bool {
+        let len = Collection::len(self);
+        if i >= len || j >= len {
+            return false;
+        }
+        if i != j {
+            // The element at j is moved out by `set`, only the one at i
+            // is cloned
+            let a = self.get(i).cloned().expect("i is in bounds");
+            let b = self.set(j, a).expect("j is in bounds");
+            self.set(i, b);
+        }
+        true
+    }
+
     fn push(&mut self, t: T) {
         let mut branch: Branch<_, _, End> = Branch::first(self.root,
                                                           &self.stash);
@@ -117,6 +411,17 @@ impl<T, M> VectorOps<T, M> for Collection<T, M>
         self.root = branch.root();
     }
 
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        // The branch keeps pointing at the end, no need to search for
+        // it again for every element.
+        let mut branch: Branch<_, _, End> = Branch::first(self.root,
+                                                          &self.stash);
+        for t in iter {
+            branch.insert(t, self.divisor, &mut self.stash);
+        }
+        self.root = branch.root();
+    }
+
     fn pop(&mut self) -> Option<T> {
         let mut branch: Branch<_, _, End> = Branch::first(self.root,
                                                           &self.stash);
@@ -125,6 +430,37 @@ impl<T, M> VectorOps<T, M> for Collection<T, M>
         ret
     }
 
+    fn pushed(&self, t: T) -> Self {
+        let mut new = self.clone_ref();
+        new.push(t);
+        new
+    }
+
+    fn inserted(&self, i: usize, t: T) -> Self {
+        let mut new = self.clone_ref();
+        new.insert(i, t);
+        new
+    }
+
+    fn removed(&self, i: usize) -> (Self, Option<T>) {
+        let mut new = self.clone_ref();
+        let t = new.remove(i);
+        (new, t)
+    }
+
+    fn last(&self) -> Option<&T> {
+        let branch: Branch<_, _, End> = Branch::first(self.root, &self.stash);
+        branch.leaf(&self.stash)
+    }
+
+    fn len(&self) -> usize {
+        Collection::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        Collection::is_empty(self)
+    }
+
     fn split(&mut self, i: usize) -> (Self, Self)
         where Self: Sized
     {
@@ -150,6 +486,68 @@ impl<T, M> VectorOps<T, M> for Collection<T, M>
         }
     }
 
+    fn truncate(&mut self, n: usize) {
+        let (head, _) = self.split(n);
+        self.adopt(head);
+    }
+
+    fn split_off(&mut self, n: usize) -> Self {
+        let (head, tail) = self.split(n);
+        self.adopt(head);
+        tail
+    }
+
+    fn dedup_by_key<K, F>(&mut self, mut f: F)
+        where K: PartialEq,
+              F: FnMut(&T) -> K
+    {
+        let mut duplicates = vec![];
+        {
+            let mut last = None;
+            for (i, t) in self.iter().enumerate() {
+                let key = f(t);
+                if last.as_ref() == Some(&key) {
+                    duplicates.push(i);
+                }
+                last = Some(key);
+            }
+        }
+        // Removing from the back keeps the remaining indices valid, and
+        // leaves the subtrees without duplicates untouched.
+        for i in duplicates.into_iter().rev() {
+            self.remove(i);
+        }
+    }
+
+    fn sorted<N>(&self) -> Collection<T, N>
+        where T: Ord,
+              N: Meta<T> + SubMeta<Max<T>>
+    {
+        let mut elements: Vec<_> = self.iter().collect();
+        elements.sort();
+        elements.dedup();
+        Collection::from_sorted(elements.into_iter().cloned())
+    }
+
+    fn reverse(&self) -> Self {
+        // The shape of the tree depends on the order of the elements, so
+        // nothing can be shared, but building it bottom-up avoids a
+        // search for every element.
+        Collection::from_sorted(self.iter_rev().cloned())
+    }
+
+    fn rotate_left(&mut self, n: usize) {
+        assert!(n <= Collection::len(self), "Rotate past length of collection");
+        let (mut head, mut tail) = self.split(n);
+        self.adopt(tail.concat(&mut head));
+    }
+
+    fn rotate_right(&mut self, n: usize) {
+        let len = Collection::len(self);
+        assert!(n <= len, "Rotate past length of collection");
+        self.rotate_left(len - n);
+    }
+
     fn concat(&mut self, b: &mut Self) -> Self {
         let mut stash =
             self.stash.merge(&mut self.root, &mut b.root, &mut b.stash);
@@ -166,6 +564,126 @@ impl<T, M> VectorOps<T, M> for Collection<T, M>
         let (mut first, mut second) = self.split(i);
         first.concat(&mut from.clone_mut()).concat(&mut second)
     }
+
+    fn splice_into(&mut self, i: usize, mut from: Self) {
+        let (mut first, mut second) = self.split(i);
+        self.adopt(first.concat(&mut from).concat(&mut second));
+    }
+
+    fn insert_all(&mut self, i: usize, items: Vec<T>) {
+        assert!(i <= Collection::len(self), "Insert past length of collection");
+        self.splice_into(i, Collection::from_sorted(items));
+    }
+
+    fn drain_range<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<T, M> {
+        let len = Collection::len(self);
+        let start = match range.start_bound() {
+            Bound::Included(&a) => a,
+            Bound::Excluded(&a) => {
+                a.checked_add(1).expect("Drain start past usize::MAX")
+            }
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&b) => {
+                b.checked_add(1).expect("Drain end past usize::MAX")
+            }
+            Bound::Excluded(&b) => b,
+            Bound::Unbounded => len,
+        };
+        assert!(start <= end, "Drain start after its end");
+        assert!(end <= len, "Drain past length of collection");
+        let (mut head, mut rest) = self.split(start);
+        let (drained, mut tail) = rest.split(end - start);
+        self.adopt(head.concat(&mut tail));
+        Drain::new(drained)
+    }
+
+    fn iter_range<'a>(&'a self,
+                      range: Range<usize>)
+                      -> Take<Iter<'a, T, M, Beginning>> {
+        self.iter_from_using(Cardinality::new(&range.start))
+            .take(range.end.saturating_sub(range.start))
+    }
+
+    fn slice<'a>(&'a self, range: Range<usize>) -> VectorSlice<'a, T, M> {
+        let len = Collection::len(self);
+        let end = cmp::min(range.end, len);
+        VectorSlice {
+            collection: self,
+            start: cmp::min(range.start, end),
+            end: end,
+        }
+    }
+
+    fn chunks<'a>(&'a self, n: usize) -> Chunks<'a, T, M> {
+        assert!(n != 0, "Chunk size must be non-zero");
+        Chunks {
+            iter: self.iter(),
+            n: n,
+        }
+    }
+
+    fn iter_indexed<'a>(&'a self) -> IndexedIter<'a, T, M> {
+        IndexedIter {
+            collection: self,
+            iter: self.iter(),
+            index: 0,
+        }
+    }
+}
+
+impl<T, M> VectorOpsMax<T> for Collection<T, M>
+    where T: Val + Ord,
+          M: Meta<T> + SubMeta<Cardinality<usize>> + SubMeta<Max<T>>
+{
+    fn binary_search(&self, t: &T) -> Result<usize, usize> {
+        let i = self.count_before(Max::from_t(t));
+        match self.get(i) {
+            Some(found) if found == t => Ok(i),
+            _ => Err(i),
+        }
+    }
+}
+
+impl<T, M> Index<usize> for Collection<T, M>
+    where T: Val,
+          M: Meta<T> + SubMeta<Cardinality<usize>>
+{
+    type Output = T;
+
+    /// Returns a reference to the element at index `i`.
+    ///
+    /// Panics if `i` is out of bounds, use `get` to handle that case.
+    fn index(&self, i: usize) -> &T {
+        match self.get(i) {
+            Some(t) => t,
+            None => {
+                panic!("index out of bounds: the len is {} but the index is {}",
+                       self.len(),
+                       i)
+            }
+        }
+    }
+}
+
+impl<'a, T, M> From<&'a Collection<T, M>> for Vec<T>
+    where T: Val,
+          M: Meta<T>
+{
+    fn from(collection: &'a Collection<T, M>) -> Self {
+        collection.iter().cloned().collect()
+    }
+}
+
+impl<T, M> From<Vec<T>> for Collection<T, M>
+    where T: Val,
+          M: Meta<T> + SubMeta<Cardinality<usize>>
+{
+    /// Builds a vector holding the elements of `vec` in the same order
+    fn from(vec: Vec<T>) -> Self {
+        Collection::from_sorted(vec)
+    }
 }
 
 #[cfg(test)]
@@ -204,6 +722,24 @@ mod tests {
         assert!(a == b);
     }
 
+    #[test]
+    fn extend() {
+        let mut a = Vector::new();
+        let mut b = Vector::new();
+
+        for i in 0..LOTS {
+            a.push(i);
+        }
+
+        b.extend(0..LOTS / 2);
+        b.extend(LOTS / 2..LOTS);
+        b.extend(None);
+
+        assert!(a == b);
+        assert!(a.iter().eq(b.iter()));
+        assert_eq!(b.get(LOTS / 3), Some(&(LOTS / 3)));
+    }
+
     #[test]
     fn indexing() {
         let mut vec = Vector::new();
@@ -219,6 +755,51 @@ mod tests {
         assert_eq!(vec.get(LOTS), None);
     }
 
+    #[test]
+    fn deep_eq() {
+        let mut a = Vector::new();
+        let mut b = Vector::new();
+
+        for i in 0..LOTS {
+            a.push(i);
+            b.push(i);
+        }
+        assert!(a.deep_eq(&b));
+        let shared = a.clone_mut();
+        assert!(a.deep_eq(&shared));
+
+        b.set(LOTS / 2, 0);
+        assert!(!a.deep_eq(&b));
+        b.pop();
+        assert!(!a.deep_eq(&b));
+        assert!(Vector::<usize>::new().deep_eq(&Vector::new()));
+
+        // Comparing by a "checksum" that collides
+        let mut c = Vector::new();
+        let mut d = Vector::new();
+        c.push(1);
+        d.push(2);
+        assert!(c.eq_using::<Cardinality<usize>>(&d));
+        assert!(!c.deep_eq(&d));
+    }
+
+    #[test]
+    fn index_operator() {
+        let vec = Vector::from((0..LOTS).map(|i| i * 2).collect::<Vec<_>>());
+
+        for i in 0..LOTS {
+            assert_eq!(vec[i], i * 2);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_operator_panic() {
+        let mut vec = Vector::new();
+        vec.push(0);
+        let _ = vec[1];
+    }
+
     #[test]
     fn remove() {
         debug_assert!(LOTS % 2 == 0);
@@ -268,6 +849,80 @@ mod tests {
         assert_eq!(vec.remove(0), None)
     }
 
+    #[test]
+    fn pop() {
+        let mut vec = Vector::new();
+        let mut reference = Vector::new();
+        let empty = Vector::new();
+
+        for i in 0..LOTS {
+            vec.push(i);
+            if i < LOTS / 2 {
+                reference.push(i);
+            }
+        }
+
+        for i in 0..LOTS / 2 {
+            assert_eq!(vec.pop(), Some(LOTS - i - 1));
+        }
+
+        assert!(vec == reference);
+
+        for i in 0..LOTS / 2 {
+            assert_eq!(vec.pop(), Some(LOTS / 2 - i - 1));
+        }
+
+        assert!(vec == empty);
+        assert_eq!(vec.pop(), None);
+    }
+
+    #[test]
+    fn persistent_updates() {
+        let mut a = Vector::new();
+
+        for i in 0..LOTS {
+            a.push(i);
+        }
+
+        // Through a shared reference, with all the nodes owned by `a`
+        let shared = &a;
+        let b = shared.pushed(LOTS);
+        let c = b.inserted(0, LOTS + 1);
+        let (d, removed) = shared.removed(LOTS / 2);
+
+        // And with all of them in shared layers
+        let _ = a.clone_mut();
+        let e = a.pushed(LOTS);
+
+        assert!(a.iter().cloned().eq(0..LOTS));
+        assert!(b.iter().cloned().eq(0..LOTS + 1));
+        let expected = Some(LOTS + 1).into_iter().chain(0..LOTS + 1);
+        assert!(c.iter().cloned().eq(expected));
+        assert!(d.iter().cloned().eq((0..LOTS).filter(|i| *i != LOTS / 2)));
+        assert_eq!(removed, Some(LOTS / 2));
+        assert!(e == b);
+    }
+
+    #[test]
+    fn len_last() {
+        let mut vec = Vector::new();
+
+        assert_eq!(VectorOps::len(&vec), 0);
+        assert!(VectorOps::is_empty(&vec));
+        assert_eq!(vec.last(), None);
+
+        for i in 0..LOTS {
+            vec.push(i);
+            assert_eq!(vec.last(), Some(&i));
+        }
+        assert_eq!(VectorOps::len(&vec), LOTS);
+        assert!(!VectorOps::is_empty(&vec));
+
+        vec.pop();
+        assert_eq!(VectorOps::len(&vec), LOTS - 1);
+        assert_eq!(vec.last(), Some(&(LOTS - 2)));
+    }
+
     #[test]
     #[should_panic]
     fn insert_panic() {
@@ -275,6 +930,22 @@ mod tests {
         vec.insert(1, 1);
     }
 
+    #[test]
+    fn try_insert() {
+        let mut vec = Vector::new();
+
+        let err = vec.try_insert(1, "a").unwrap_err();
+        assert_eq!(err.index(), 1);
+        assert_eq!(err.into_inner(), "a");
+
+        assert_eq!(vec.try_insert(0, "a"), Ok(()));
+        assert_eq!(vec.try_insert(1, "c"), Ok(()));
+        assert_eq!(vec.try_insert(1, "b"), Ok(()));
+        assert!(vec.try_insert(4, "d").is_err());
+
+        assert_eq!(vec.iter().collect::<Vec<_>>(), vec![&"a", &"b", &"c"]);
+    }
+
     #[test]
     fn partial_equal() {
         let mut vec_a = Vector::new();
@@ -342,32 +1013,84 @@ mod tests {
     }
 
     #[test]
-    fn concat() {
+    fn truncate_split_off() {
         let mut vec = Vector::new();
 
         for i in 0..LOTS {
             vec.push(i)
         }
 
-        let mut split_points = vec![1];
+        let mut tail = vec.split_off(LOTS / 2);
+        assert_eq!(vec.iter().count(), LOTS / 2);
+        assert_eq!(tail.iter().next(), Some(&(LOTS / 2)));
+        assert_eq!(tail.iter().count(), LOTS - LOTS / 2);
 
-        for i in 0..100 {
-            split_points.push((LOTS / 100) * i);
-        }
-        split_points.push(LOTS - 1);
-        split_points.push(LOTS);
+        tail.truncate(10);
+        assert_eq!(tail.iter().cloned().collect::<Vec<_>>(),
+                   (LOTS / 2..LOTS / 2 + 10).collect::<Vec<_>>());
 
-        for i in split_points {
-            let (mut a, mut b) = vec.split(i);
-            let c = a.concat(&mut b);
-            assert!(c == vec);
+        tail.truncate(20);
+        assert_eq!(tail.iter().count(), 10);
+
+        assert_eq!(tail.split_off(10).iter().next(), None);
+        tail.truncate(0);
+        assert_eq!(tail.iter().next(), None);
+    }
+
+    #[test]
+    fn rotate() {
+        let mut vec = Vector::new();
+
+        for i in 0..LOTS {
+            vec.push(i);
         }
+
+        vec.rotate_left(10);
+        assert!(vec.iter().cloned().eq((10..LOTS).chain(0..10)));
+
+        vec.rotate_right(10);
+        assert!(vec.iter().cloned().eq(0..LOTS));
+
+        vec.rotate_left(0);
+        vec.rotate_right(LOTS);
+        assert!(vec.iter().cloned().eq(0..LOTS));
     }
 
     #[test]
-    fn concat_comprehensive() {
-        for lots in 0..QUADRATIC {
-            let mut vec = Vector::new();
+    #[should_panic]
+    fn rotate_panic() {
+        let mut vec = Vector::new();
+        vec.push(0);
+        vec.rotate_left(2);
+    }
+
+    #[test]
+    fn concat() {
+        let mut vec = Vector::new();
+
+        for i in 0..LOTS {
+            vec.push(i)
+        }
+
+        let mut split_points = vec![1];
+
+        for i in 0..100 {
+            split_points.push((LOTS / 100) * i);
+        }
+        split_points.push(LOTS - 1);
+        split_points.push(LOTS);
+
+        for i in split_points {
+            let (mut a, mut b) = vec.split(i);
+            let c = a.concat(&mut b);
+            assert!(c == vec);
+        }
+    }
+
+    #[test]
+    fn concat_comprehensive() {
+        for lots in 0..QUADRATIC {
+            let mut vec = Vector::new();
             for i in 0..lots {
                 vec.push(i)
             }
@@ -399,6 +1122,346 @@ mod tests {
         assert!(spliced == reference);
     }
 
+    #[test]
+    fn splice_into() {
+        let mut into = Vector::new();
+        let mut splice_in = Vector::new();
+        let mut reference = Vector::new();
+
+        for i in 0..LOTS {
+            if !(LOTS / 3..=LOTS / 3 * 2).contains(&i) {
+                into.push(i);
+            } else {
+                splice_in.push(i);
+            }
+            reference.push(i);
+        }
+
+        into.splice_into(LOTS / 3, splice_in);
+
+        assert!(into == reference);
+        assert!(into.iter().eq(reference.iter()));
+
+        into.splice_into(0, Vector::new());
+        assert!(into == reference);
+    }
+
+    #[test]
+    fn insert_all() {
+        let mut vec = Vector::new();
+        let mut reference = Vector::new();
+
+        for i in 0..LOTS {
+            if !(LOTS / 3..LOTS / 3 * 2).contains(&i) {
+                vec.push(i);
+            }
+            reference.push(i);
+        }
+
+        vec.insert_all(LOTS / 3, (LOTS / 3..LOTS / 3 * 2).collect());
+        assert!(vec == reference);
+        assert!(vec.iter().eq(reference.iter()));
+
+        vec.insert_all(LOTS, vec![]);
+        vec.insert_all(LOTS, vec![LOTS]);
+        reference.push(LOTS);
+        assert!(vec == reference);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_all_panic() {
+        let mut vec = Vector::new();
+        vec.insert_all(1, vec![1]);
+    }
+
+    #[test]
+    fn drain_range() {
+        let mut vec = Vector::new();
+        let mut reference = Vector::new();
+
+        for i in 0..LOTS {
+            vec.push(i);
+            if !(LOTS / 3..(LOTS / 3) * 2).contains(&i) {
+                reference.push(i);
+            }
+        }
+
+        let mut count = LOTS / 3;
+        for t in vec.drain_range(LOTS / 3..(LOTS / 3) * 2) {
+            assert_eq!(t, count);
+            count += 1;
+        }
+        assert_eq!(count, (LOTS / 3) * 2);
+
+        assert!(vec == reference);
+    }
+
+    #[test]
+    fn drain_range_bounds() {
+        let mut vec = Vector::new();
+
+        for i in 0..10 {
+            vec.push(i);
+        }
+
+        assert_eq!(vec.drain_range(10..).count(), 0);
+        assert_eq!(vec.drain_range(8..).collect::<Vec<_>>(), vec![8, 9]);
+        assert_eq!(vec.drain_range(..=1).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(vec.drain_range(2..2).count(), 0);
+        assert_eq!(vec.drain_range(..).collect::<Vec<_>>(),
+                   vec![2, 3, 4, 5, 6, 7]);
+        assert_eq!(vec.iter().next(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn drain_range_past_end() {
+        let mut vec = Vector::new();
+        vec.push(0);
+        vec.drain_range(2..);
+    }
+
+    #[test]
+    #[should_panic]
+    fn drain_range_to_max() {
+        let mut vec = Vector::new();
+        vec.push(0);
+        vec.drain_range(..=usize::MAX);
+    }
+
+    #[test]
+    #[should_panic]
+    fn drain_range_reversed() {
+        let mut vec = Vector::new();
+        vec.push(0);
+        vec.push(1);
+        let (a, b) = (1, 0);
+        vec.drain_range(a..b);
+    }
+
+    #[test]
+    fn drain_shared_layers() {
+        let mut vec = Vector::new();
+        let mut copies = vec![];
+
+        // Every `clone_mut` moves the nodes one layer further down, so
+        // nodes in shared layers point to nodes in deeper ones.
+        for i in 0..LOTS {
+            vec.push(i);
+            if i % (LOTS / 4) == 0 {
+                copies.push(vec.clone_mut());
+            }
+        }
+
+        let mut count = 0;
+        for t in vec.drain() {
+            assert_eq!(t, count);
+            count += 1;
+        }
+        assert_eq!(count, LOTS);
+
+        for (n, mut copy) in copies.into_iter().enumerate() {
+            let len = n * (LOTS / 4) + 1;
+            for i in 0..len {
+                assert_eq!(copy.remove(0), Some(i));
+            }
+            assert!(copy.is_empty());
+        }
+    }
+
+    #[test]
+    fn set() {
+        let mut a = Vector::new();
+        let mut b = Vector::new();
+
+        for i in 0..LOTS {
+            a.push(i);
+            b.push(i + 1);
+        }
+
+        for i in 0..LOTS {
+            assert_eq!(b.set(i, i), Some(i + 1));
+        }
+        assert_eq!(b.set(LOTS, LOTS), None);
+
+        assert!(a == b);
+    }
+
+    #[test]
+    fn set_last() {
+        let mut vec = Vector::new();
+        let mut reference = Vector::new();
+
+        for i in 0..100 {
+            vec.push(i);
+        }
+        for i in 0..99 {
+            reference.push(i);
+        }
+        reference.push(999);
+
+        // Changes the weight of the last element back and forth. It
+        // must never split off a node after it, as there is nothing to
+        // put in that node.
+        for j in 0..1000 {
+            vec.set(99, j);
+            assert_eq!(vec.len(), 100);
+        }
+
+        assert!(vec == reference);
+    }
+
+    #[test]
+    fn swap() {
+        let mut vec = Vector::new();
+        let mut reversed = Vector::new();
+
+        for i in 0..LOTS {
+            vec.push(i);
+            reversed.push(LOTS - i - 1);
+        }
+
+        for i in 0..LOTS / 2 {
+            assert!(vec.swap(i, LOTS - i - 1));
+        }
+        assert!(vec.swap(3, 3));
+        assert!(!vec.swap(0, LOTS));
+
+        assert!(vec == reversed);
+        assert!(vec.iter().eq(reversed.iter()));
+    }
+
+    #[test]
+    fn dedup() {
+        let mut vec = Vector::new();
+        let mut reference = Vector::new();
+
+        for i in 0..LOTS {
+            for _ in 0..i % 3 + 1 {
+                vec.push(i);
+            }
+            reference.push(i);
+        }
+        vec.push(0);
+        reference.push(0);
+
+        vec.dedup();
+        assert!(vec == reference);
+        assert!(vec.iter().eq(reference.iter()));
+
+        vec.dedup_by_key(|t| t / 10);
+        let tens = (0..LOTS / 10).map(|i| i * 10).chain(Some(0));
+        assert!(vec.iter().cloned().eq(tens));
+    }
+
+    #[test]
+    fn reverse() {
+        let mut vec = Vector::new();
+        let mut reversed = Vector::new();
+
+        for i in 0..LOTS {
+            vec.push(i);
+            reversed.push(LOTS - i - 1);
+        }
+
+        assert!(vec.reverse() == reversed);
+        assert!(vec.reverse().iter().eq(reversed.iter()));
+        assert!(vec.reverse().reverse() == vec);
+        assert!(Vector::<usize>::new().reverse().iter().next().is_none());
+    }
+
+    #[test]
+    fn iter_range() {
+        let mut vec = Vector::new();
+
+        for i in 0..LOTS {
+            vec.push(i);
+        }
+
+        for start in 0..SPLITS {
+            let start = (start * LOTS) / SPLITS;
+            let end = start + LOTS / SPLITS / 2;
+            let range: Vec<_> = vec.iter_range(start..end).cloned().collect();
+            assert_eq!(range, (start..end).collect::<Vec<_>>());
+        }
+
+        assert_eq!(vec.iter_range(LOTS - 1..LOTS + 1).count(), 1);
+        assert_eq!(vec.iter_range(LOTS..LOTS + 1).count(), 0);
+        let (a, b) = (10, 5);
+        assert_eq!(vec.iter_range(a..b).count(), 0);
+        assert_eq!(Vector::<usize>::new().iter_range(0..5).count(), 0);
+    }
+
+    #[test]
+    fn slice() {
+        let mut vec = Vector::new();
+
+        for i in 0..LOTS {
+            vec.push(i);
+        }
+
+        let slice = vec.slice(LOTS / 2..LOTS / 2 + 100);
+        assert_eq!(slice.len(), 100);
+        assert_eq!(slice.get(0), Some(&(LOTS / 2)));
+        assert_eq!(slice.get(99), Some(&(LOTS / 2 + 99)));
+        assert_eq!(slice.get(100), None);
+        assert!(slice.iter().cloned().eq(LOTS / 2..LOTS / 2 + 100));
+
+        let slice = vec.slice(LOTS - 1..LOTS + 10);
+        assert_eq!(slice.len(), 1);
+        assert_eq!(slice.iter().count(), 1);
+
+        assert!(vec.slice(LOTS + 1..LOTS + 10).is_empty());
+        let (a, b) = (10, 5);
+        assert!(vec.slice(a..b).is_empty());
+    }
+
+    #[test]
+    fn chunks() {
+        let mut vec = Vector::new();
+
+        for i in 0..LOTS + 3 {
+            vec.push(i);
+        }
+
+        let mut count = 0;
+        for chunk in vec.chunks(10) {
+            for t in chunk {
+                assert_eq!(*t, count);
+                count += 1;
+            }
+        }
+        assert_eq!(count, LOTS + 3);
+        assert_eq!(vec.chunks(10).last().map(|c| c.len()), Some(3));
+        assert_eq!(Vector::<usize>::new().chunks(10).next(), None);
+    }
+
+    #[test]
+    fn iter_indexed() {
+        let mut vec = Vector::new();
+
+        for i in 0..LOTS {
+            vec.push(i * 2);
+        }
+
+        let mut iter = vec.iter_indexed();
+        for i in 0..LOTS / 2 {
+            assert_eq!(iter.next(), Some((i, &(i * 2))));
+        }
+
+        iter.seek(LOTS - 2);
+        assert_eq!(iter.next(), Some((LOTS - 2, &((LOTS - 2) * 2))));
+        assert_eq!(iter.next(), Some((LOTS - 1, &((LOTS - 1) * 2))));
+        assert_eq!(iter.next(), None);
+
+        iter.seek(1);
+        assert_eq!(iter.next(), Some((1, &2)));
+
+        iter.seek(LOTS);
+        assert_eq!(iter.next(), None);
+    }
+
     #[test]
     fn mutate() {
         let mut a = Vector::new();
@@ -415,4 +1478,365 @@ mod tests {
 
         assert!(a == b);
     }
+
+    #[test]
+    fn std_vec() {
+        let std: Vec<usize> = (0..LOTS).map(|i| i * 7 % 13).collect();
+
+        let vec = Vector::from(std.clone());
+        assert_eq!(vec.len(), LOTS);
+        for (i, t) in std.iter().enumerate() {
+            assert_eq!(vec.get(i), Some(t));
+        }
+        assert_eq!(Vec::from(&vec), std);
+
+        let empty = Vector::<usize>::from(vec![]);
+        assert!(empty.is_empty());
+        assert_eq!(Vec::from(&empty), vec![]);
+    }
+
+    mod sorted {
+        use meta::cardinality::Cardinality;
+        use meta::max::Max;
+        use collection::Collection;
+        use ops::set::SetOps;
+        use super::super::{VectorOps, VectorOpsMax};
+        use super::{Vector, LOTS};
+
+        collection!(SortedVector<T> {
+            cardinality: Cardinality<usize>,
+            max: Max<T>,
+        } where T: Ord);
+
+        #[test]
+        fn binary_search() {
+            let mut vec = SortedVector::new();
+            let mut reference = vec![];
+
+            assert_eq!(vec.binary_search(&0), Err(0));
+
+            for i in 0..LOTS {
+                vec.push(i * 2);
+                reference.push(i * 2);
+            }
+
+            for i in 0..LOTS * 2 + 1 {
+                assert_eq!(vec.binary_search(&i), reference.binary_search(&i));
+            }
+        }
+
+        #[test]
+        fn sorted() {
+            let mut vec = Vector::new();
+            let mut reference = SortedVector::new();
+
+            for i in 0..LOTS {
+                vec.push((i * 7919) % LOTS);
+                vec.push((i * 7919) % LOTS);
+                SetOps::insert(&mut reference, i);
+            }
+
+            let sorted: SortedVector<_> = vec.sorted();
+            assert!(sorted.iter().cloned().eq(0..LOTS));
+            assert!(sorted.iter().eq(reference.iter()));
+            assert!(sorted.member(&(LOTS / 2)));
+        }
+    }
+
+    mod summed {
+        use meta::cardinality::Cardinality;
+        use meta::sum::{Sum, Summed, Mean};
+        use collection::Collection;
+        use super::super::VectorOps;
+        use super::LOTS;
+
+        collection!(SumVector<T> {
+            cardinality: Cardinality<usize>,
+            sum: Sum<T::Summand>,
+            mean: Mean<T::Summand>,
+        } where T: Summed);
+
+        #[derive(Clone, Hash)]
+        struct Order {
+            id: usize,
+            price: u32,
+        }
+
+        impl Summed for Order {
+            type Summand = u32;
+
+            fn summand(&self) -> u32 {
+                self.price
+            }
+        }
+
+        #[test]
+        fn query_range() {
+            let mut vec = SumVector::new();
+
+            for i in 0..LOTS {
+                vec.push(i);
+            }
+
+            let ranges = vec![0..LOTS, 0..1, 3..4, 10..LOTS - 10,
+                              LOTS / 3..LOTS / 2, LOTS - 1..LOTS + 5];
+            for range in ranges {
+                let sum: Option<Sum<usize>> = vec.query_range(range.clone());
+                let expected: usize = range.filter(|i| *i < LOTS).sum();
+                assert_eq!(sum.map(|s| *s.inner()), Some(expected));
+            }
+
+            let empty: Option<Sum<usize>> = vec.query_range(5..5);
+            assert!(empty.is_none());
+            let past: Option<Sum<usize>> = vec.query_range(LOTS..LOTS + 1);
+            assert!(past.is_none());
+        }
+
+        #[test]
+        fn projected_sum() {
+            let mut orders = SumVector::new();
+
+            for i in 0..LOTS {
+                orders.push(Order {
+                    id: i,
+                    price: (i % 7) as u32,
+                });
+            }
+
+            let total: Option<Sum<u32>> = orders.query_range(0..LOTS);
+            let expected: u32 = (0..LOTS).map(|i| (i % 7) as u32).sum();
+            assert_eq!(total.map(|s| *s.inner()), Some(expected));
+            assert_eq!(orders.get(3).map(|o| o.id), Some(3));
+        }
+
+        #[test]
+        fn mean() {
+            let mut orders = SumVector::new();
+
+            for i in 0..LOTS {
+                orders.push(Order {
+                    id: i,
+                    price: (i % 2) as u32 * 10,
+                });
+            }
+
+            let mean: Mean<u32> = orders.query_range(0..LOTS).unwrap();
+            assert_eq!(mean.count(), LOTS);
+            assert_eq!(mean.mean(), 5.0);
+
+            let mean: Mean<u32> = orders.query_range(1..4).unwrap();
+            assert_eq!(*mean.sum(), 20);
+            assert!((mean.mean() - 20.0 / 3.0).abs() < 1e-9);
+
+            let mut vec = SumVector::new();
+            for i in 0..LOTS as u64 {
+                vec.push(i);
+            }
+            let mean: Mean<u64> = vec.query_range(0..LOTS).unwrap();
+            assert_eq!(mean.mean(), (LOTS - 1) as f64 / 2.0);
+        }
+    }
+
+    mod wide {
+        use std::marker::PhantomData;
+
+        use meta::Meta;
+        use meta::cardinality::Cardinality;
+        use collection::Collection;
+        use super::LOTS;
+
+        collection!(WideVector<T> {
+            cardinality: Cardinality<u64>,
+        } where T: Clone);
+
+        #[test]
+        fn u64_index() {
+            let vec = WideVector::from_sorted(0..LOTS);
+
+            let from = LOTS as u64 / 2;
+            let mut iter = vec.iter_from_using(Cardinality::new(&from));
+            assert_eq!(iter.next(), Some(&(LOTS / 2)));
+        }
+
+        #[test]
+        fn fixed_widths() {
+            let mut a: Cardinality<u32> = Meta::<usize>::from_t(&0);
+            let b: Cardinality<u32> = Meta::<usize>::from_t(&1);
+            Meta::<usize>::merge(&mut a, &b, PhantomData);
+            assert_eq!(*a.inner(), 2);
+
+            let c: Cardinality<u128> = Meta::<usize>::from_t(&0);
+            assert_eq!(*c.inner(), 1);
+        }
+    }
+
+    mod aggregated {
+        use std::cmp;
+
+        use meta::cardinality::Cardinality;
+        use meta::map_meta::{Aggregate, MapMeta};
+        use collection::Collection;
+        use super::super::VectorOps;
+        use super::LOTS;
+
+        // The length of the longest string
+        pub struct Longest;
+
+        impl Aggregate for Longest {
+            type T = String;
+            type Acc = usize;
+
+            fn project(t: &String) -> usize {
+                t.len()
+            }
+            fn merge(a: &mut usize, b: &usize) {
+                *a = cmp::max(*a, *b)
+            }
+        }
+
+        collection!(Strings<T> {
+            cardinality: Cardinality<usize>,
+            longest: MapMeta<Longest>,
+        } where MapMeta<Longest>: Meta<T>);
+
+        #[test]
+        fn map_meta() {
+            let mut vec = Strings::new();
+
+            for i in 0..LOTS {
+                vec.push(format!("{}", i % 1000));
+            }
+            vec.insert(LOTS / 2, String::from("longest"));
+
+            let all: Option<MapMeta<Longest>> = vec.query_range(0..LOTS + 1);
+            assert_eq!(all.map(|m| *m.inner()), Some(7));
+
+            let head: Option<MapMeta<Longest>> = vec.query_range(0..100);
+            assert_eq!(head.map(|m| *m.inner()), Some(2));
+        }
+    }
+
+    mod bucketed {
+        use meta::cardinality::Cardinality;
+        use meta::histogram::{Histogram, Bucketed};
+        use collection::Collection;
+        use super::super::VectorOps;
+        use super::LOTS;
+
+        collection!(Latencies<T> {
+            cardinality: Cardinality<usize>,
+            histogram: Histogram<[usize; 10]>,
+        } where T: Bucketed);
+
+        #[derive(Clone, Hash)]
+        struct Millis(usize);
+
+        // Buckets of 100ms
+        impl Bucketed for Millis {
+            fn bucket(&self) -> usize {
+                self.0 / 100
+            }
+        }
+
+        #[test]
+        fn histogram() {
+            let mut vec = Latencies::new();
+
+            for i in 0..LOTS {
+                vec.push(Millis(i % 1000));
+            }
+            vec.push(Millis(5000));
+
+            let all: Histogram<[usize; 10]> =
+                vec.query_range(0..LOTS + 1).unwrap();
+            assert_eq!(all.total(), LOTS + 1);
+            assert_eq!(all.counts()[0], LOTS / 10);
+            assert_eq!(all.counts()[9], LOTS / 10 + 1);
+            assert_eq!(all.percentile(50.0), Some(5));
+            assert_eq!(all.percentile(99.0), Some(9));
+            assert_eq!(all.percentile(0.0), Some(0));
+
+            let head: Histogram<[usize; 10]> = vec.query_range(0..150).unwrap();
+            assert_eq!(head.counts()[1], 50);
+            assert_eq!(head.percentile(100.0), Some(1));
+        }
+    }
+
+    mod ranked {
+        use meta::cardinality::Cardinality;
+        use meta::top_k::TopK;
+        use collection::Collection;
+        use super::super::VectorOps;
+        use super::LOTS;
+
+        collection!(RankedVector<T> {
+            cardinality: Cardinality<usize>,
+            top: TopK<[T; 10]>,
+        } where T: Ord);
+
+        #[test]
+        fn top_k() {
+            let mut vec = RankedVector::new();
+
+            assert_eq!(vec.top_k::<[usize; 10]>(), vec![]);
+
+            for i in 0..LOTS {
+                vec.push((i * 7919) % LOTS);
+            }
+            let expected: Vec<_> = (LOTS - 10..LOTS).rev().collect();
+            assert_eq!(vec.top_k::<[usize; 10]>(), expected);
+
+            vec.remove(vec.iter().position(|t| *t == LOTS - 1).unwrap());
+            let expected: Vec<_> = (LOTS - 11..LOTS - 1).rev().collect();
+            assert_eq!(vec.top_k::<[usize; 10]>(), expected);
+
+            let mut small = RankedVector::new();
+            small.push(3);
+            small.push(5);
+            small.push(3);
+            assert_eq!(small.top_k::<[usize; 10]>(), vec![5, 3, 3]);
+        }
+    }
+
+    mod hashed {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hash;
+
+        use meta::cardinality::Cardinality;
+        use meta::checksum::{CheckSum, CheckSumWith};
+        use collection::Collection;
+        use super::super::VectorOps;
+        use super::{LOTS, Vector};
+
+        collection!(HashedVector<T> {
+            cardinality: Cardinality<usize>,
+            checksum: CheckSumWith<DefaultHasher>,
+        } where T: Hash);
+
+        #[test]
+        fn rehash() {
+            let mut vec = Vector::new();
+
+            for i in 0..LOTS {
+                vec.push(i * 3 % 7);
+            }
+            vec.remove(11);
+
+            let hashed: HashedVector<usize> = vec.rehash();
+            assert!(hashed.iter().eq(vec.iter()));
+            assert_eq!(hashed.len(), LOTS - 1);
+            assert_eq!(hashed.validate_using::<CheckSumWith<DefaultHasher>>(),
+                       vec![]);
+
+            let mut other: HashedVector<usize> = vec.rehash();
+            assert!(hashed.eq_using::<CheckSumWith<DefaultHasher>>(&other));
+            other.set(0, 1);
+            assert!(!hashed.eq_using::<CheckSumWith<DefaultHasher>>(&other));
+
+            let back: Vector<usize> = hashed.rehash();
+            assert!(back == vec);
+            assert_eq!(back.validate_using::<CheckSum<u64>>(), vec![]);
+            assert_eq!(back.stats().depth, vec.stats().depth);
+        }
+    }
 }
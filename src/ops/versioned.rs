@@ -0,0 +1,120 @@
+use std::borrow::Cow;
+
+use Val;
+
+use collection::Collection;
+
+use meta::{Meta, SubMeta};
+use meta::version::{Version, Versioned};
+
+use stash::Location;
+
+use tree::node::Child;
+
+/// Operations on collections with `Version` metadata
+pub trait VersionOps<T>
+    where T: Versioned
+{
+    /// Returns the elements with a version greater than `version`, in
+    /// order.
+    ///
+    /// Subtrees without such elements are skipped, so the work done is
+    /// proportional to the number of modified elements.
+    fn modified_since(&self, version: u64) -> Vec<&T>;
+}
+
+impl<T, M> VersionOps<T> for Collection<T, M>
+    where T: Val + Versioned,
+          M: Meta<T> + SubMeta<Version<u64>>
+{
+    fn modified_since(&self, version: u64) -> Vec<&T> {
+        let mut found = vec![];
+        modified_since(self, self.root, version, &mut found);
+        found
+    }
+}
+
+// Collects the elements below `location` newer than `version`
+fn modified_since<'a, T, M>(collection: &'a Collection<T, M>,
+                            location: Location<T, M>,
+                            version: u64,
+                            found: &mut Vec<&'a T>)
+    where T: Val + Versioned,
+          M: Meta<T> + SubMeta<Version<u64>>
+{
+    let node = collection.stash.get(location);
+    for child in &node.children {
+        match *child {
+            Child::Node { location: child_location, ref meta } => {
+                let max: Cow<Version<u64>> = meta.submeta();
+                if *max.inner() > version {
+                    modified_since(collection,
+                                   child_location.relative(location.depth),
+                                   version,
+                                   found);
+                }
+            }
+            Child::Leaf(ref t) => {
+                if t.version() > version {
+                    found.push(t);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    const LOTS: usize = 100_000;
+
+    use std::hash::Hash;
+
+    use meta::key::{Key, Keyed};
+    use meta::version::{Version, Versioned};
+
+    use collection::Collection;
+
+    use ops::map::{MapOps, KV};
+
+    use super::VersionOps;
+
+    collection!(VersionedMap<T> {
+        key: Key<T::Key>,
+        version: Version<u64>,
+    } where T: Keyed + Versioned, T::Key: Hash);
+
+    impl<K> Versioned for KV<K, (u64, usize)>
+        where K: ::Val + Ord
+    {
+        fn version(&self) -> u64 {
+            self.value().0
+        }
+    }
+
+    #[test]
+    fn modified_since() {
+        let mut map = VersionedMap::new();
+
+        for i in 0..LOTS {
+            map.insert(i, (0, i));
+        }
+
+        let mut clock = 0;
+        for i in 0..LOTS / 1000 {
+            clock += 1;
+            map.insert(i * 997, (clock, i));
+        }
+
+        assert!(map.modified_since(clock).is_empty());
+        assert_eq!(map.modified_since(0).len(), LOTS / 1000);
+
+        let since: Vec<_> = map.modified_since(clock - 10)
+            .into_iter()
+            .map(|kv| *kv.key())
+            .collect();
+        let expected: Vec<_> = (LOTS / 1000 - 10..LOTS / 1000)
+            .map(|i| i * 997)
+            .collect();
+        assert_eq!(since, expected);
+    }
+}
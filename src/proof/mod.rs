@@ -0,0 +1,729 @@
+use std::borrow::Cow;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use Val;
+use collection::Collection;
+use meta::{Meta, SubMeta};
+use meta::max::Max;
+use meta::cardinality::Cardinality;
+use meta::digest::Digest;
+use ops::set::SetOps;
+use stash::Location;
+use tree::node::Child;
+
+/// A proof that an element is part of a collection, consisting of the
+/// digests of the siblings of every node along the branch down to the
+/// element.
+///
+/// The digest `S` is a `ProofDigest`, such as `Digest` with a
+/// cryptographic hasher. The proof can be checked by anyone knowing only
+/// the digest of the root.
+#[derive(Clone)]
+pub struct Proof<S> {
+    // From the node holding the element, up to the root
+    levels: Vec<Level<S>>,
+}
+
+#[derive(Clone)]
+struct Level<S> {
+    // The digests of the children before the one on the branch
+    before: Vec<S>,
+    // The digests of the children after the one on the branch
+    after: Vec<S>,
+}
+
+/// A proof that a range of a set contains exactly the elements in the
+/// proof, and no others.
+///
+/// Holds the elements in the range, along with the ones right before and
+/// after it, and the digests of all subtrees outside of it.
+#[derive(Clone)]
+pub struct RangeProof<T, S> {
+    // The children of the root
+    parts: Vec<Part<T, S>>,
+}
+
+#[derive(Clone)]
+enum Part<T, S> {
+    // A subtree outside of the range, represented by its digest
+    Pruned(S),
+    // An element in, or right next to, the range
+    Leaf(T),
+    // A subtree overlapping the range
+    Node(Vec<Part<T, S>>),
+}
+
+/// A digest that proofs can be made with.
+///
+/// It has to be order dependent, and the digest of a subtree must never
+/// equal the digest of a single element; otherwise an element made up
+/// from the digests of other subtrees could be proven to be in the
+/// collection. `Digest` commits to the number of elements in every
+/// subtree, which is only one for a single element, so `CheckSum` and
+/// `CheckSumWith` do not qualify.
+pub trait ProofDigest<T>: Meta<T> + PartialEq
+    where T: Val
+{
+}
+
+impl<T, H> ProofDigest<T> for Digest<H>
+    where T: Val + Hash,
+          H: Hasher + Default
+{
+}
+
+// What to do with a child when looking for the branch to prove
+enum Pick {
+    // The element is further on
+    Skip,
+    // The element is in, or is, this child
+    Take,
+    // There is no such element
+    Stop,
+}
+
+impl<S> Proof<S> {
+    /// Constructs a proof from the digests of the siblings before and
+    /// after the branch at every level, from the node holding the element
+    /// up to the root, as returned by `into_levels`
+    pub fn from_levels(levels: Vec<(Vec<S>, Vec<S>)>) -> Self {
+        Proof {
+            levels: levels.into_iter()
+                .map(|(before, after)| {
+                    Level {
+                        before: before,
+                        after: after,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the digests of the siblings before and after the branch at
+    /// every level, to send the proof to whoever is to verify it
+    pub fn into_levels(self) -> Vec<(Vec<S>, Vec<S>)> {
+        self.levels
+            .into_iter()
+            .map(|level| (level.before, level.after))
+            .collect()
+    }
+
+    /// Verifies that `t` is part of the collection with the root digest
+    /// `root`.
+    ///
+    /// Only the proof itself is needed, not the collection, nor any of
+    /// its nodes.
+    pub fn verify<T>(&self, root: &S, t: &T) -> bool
+        where T: Val,
+              S: ProofDigest<T>
+    {
+        self.root(t) == *root
+    }
+
+    /// Recomputes the digest of the root from the element `t`, and the
+    /// digests of the siblings along the branch.
+    pub fn root<T>(&self, t: &T) -> S
+        where T: Val,
+              S: ProofDigest<T>
+    {
+        let mut digest = S::from_t(t);
+        for level in &self.levels {
+            let mut acc: Option<S> = None;
+            for s in level.before
+                .iter()
+                .chain(Some(&digest))
+                .chain(level.after.iter()) {
+                match acc {
+                    Some(ref mut acc) => acc.merge(s, PhantomData),
+                    None => acc = Some(s.clone()),
+                }
+            }
+            digest = acc.expect("the branch is always there");
+        }
+        digest
+    }
+}
+
+impl<T, S> RangeProof<T, S> {
+    /// Verifies the proof against the root digest `root`, returning the
+    /// elements `t` of the set where `from <= t < to`, or `None` if the
+    /// proof is not valid for the range.
+    pub fn verify(&self, root: &S, from: &T, to: &T) -> Option<Vec<&T>>
+        where T: Val + Ord,
+              S: ProofDigest<T>
+    {
+        self.elements(root, from, to).map(|elements| {
+            elements.into_iter()
+                .filter(|t| *t >= from && *t < to)
+                .collect()
+        })
+    }
+
+    /// Verifies that `t` is not in the set with the root digest `root`
+    pub fn verify_absent(&self, root: &S, t: &T) -> bool
+        where T: Val + Ord,
+              S: ProofDigest<T>
+    {
+        match self.elements(root, t, t) {
+            Some(elements) => !elements.contains(&t),
+            None => false,
+        }
+    }
+
+    // Returns all the elements in the proof, if it is valid for the root
+    // digest, and no pruned subtree can hold elements `t` where
+    // `from <= t < to`, or `t == from` for an empty range.
+    fn elements(&self, root: &S, from: &T, to: &T) -> Option<Vec<&T>>
+        where T: Val + Ord,
+              S: ProofDigest<T>
+    {
+        if fold(&self.parts).as_ref() != Some(root) {
+            return None;
+        }
+
+        // The elements in order, `None` for pruned subtrees
+        let mut items = vec![];
+        flatten(&self.parts, &mut items);
+
+        // Pruned subtrees are only allowed before an element less than
+        // `from`, or after one not less than `to`
+        let below = items.iter()
+            .rposition(|item| item.is_some_and(|t| t < from));
+        let above = items.iter()
+            .position(|item| item.is_some_and(|t| t >= to));
+        for (i, item) in items.iter().enumerate() {
+            if item.is_none() && below.is_none_or(|b| i > b) &&
+               above.is_none_or(|a| i < a) {
+                return None;
+            }
+        }
+
+        Some(items.into_iter().flatten().collect())
+    }
+}
+
+// Recomputes the digest of a node from its parts
+fn fold<T, S>(parts: &[Part<T, S>]) -> Option<S>
+    where T: Val,
+          S: ProofDigest<T>
+{
+    let mut acc = None;
+    for part in parts {
+        let s = match *part {
+            Part::Pruned(ref s) => s.clone(),
+            Part::Leaf(ref t) => S::from_t(t),
+            Part::Node(ref parts) => fold(parts)?,
+        };
+        match acc {
+            Some(ref mut acc) => S::merge(acc, &s, PhantomData),
+            None => acc = Some(s),
+        }
+    }
+    acc
+}
+
+// Collects the elements of `parts` in order into `items`, with `None`
+// for every pruned subtree
+fn flatten<'a, T, S>(parts: &'a [Part<T, S>],
+                     items: &mut Vec<Option<&'a T>>) {
+    for part in parts {
+        match *part {
+            Part::Pruned(_) => items.push(None),
+            Part::Leaf(ref t) => items.push(Some(t)),
+            Part::Node(ref parts) => flatten(parts, items),
+        }
+    }
+}
+
+impl<H> Proof<Digest<H>> {
+    /// Returns the index of the element in the collection, the number
+    /// of elements before it, as committed to by the digests
+    pub fn index(&self) -> usize {
+        self.levels
+            .iter()
+            .flat_map(|level| level.before.iter())
+            .map(|digest| digest.count())
+            .sum()
+    }
+
+    /// Verifies that `t` is at index `i` of the collection with the root
+    /// digest `root`
+    pub fn verify_at<T>(&self, root: &Digest<H>, i: usize, t: &T) -> bool
+        where T: Val,
+              Digest<H>: ProofDigest<T>
+    {
+        self.index() == i && self.verify(root, t)
+    }
+}
+
+impl<T, M> Collection<T, M>
+    where T: Val,
+          M: Meta<T>
+{
+    /// Returns the digest `S` of the whole collection, the metadata of
+    /// the root node, or `None` if the collection is empty
+    pub fn digest<S>(&self) -> Option<S>
+        where S: Clone,
+              M: SubMeta<S>
+    {
+        self.stash.get(self.root).meta().map(|meta| {
+            let s: Cow<S> = meta.submeta();
+            s.into_owned()
+        })
+    }
+
+    // Builds a proof for the branch down to the leaf that `pick` takes,
+    // `pick` is called on children in order, until it takes one.
+    fn prove_by<S, F>(&self, mut pick: F) -> Option<Proof<S>>
+        where S: ProofDigest<T>,
+              M: SubMeta<S>,
+              F: FnMut(&Child<T, M>) -> Pick
+    {
+        let mut levels = vec![];
+        let mut location = self.root;
+
+        'descend: loop {
+            let node = self.stash.get(location);
+            for (i, child) in node.children.iter().enumerate() {
+                match pick(child) {
+                    Pick::Skip => continue,
+                    Pick::Stop => return None,
+                    Pick::Take => (),
+                }
+                levels.push(Level {
+                    before: node.children
+                        .iter()
+                        .take(i)
+                        .map(digest_of)
+                        .collect(),
+                    after: node.children
+                        .iter()
+                        .skip(i + 1)
+                        .map(digest_of)
+                        .collect(),
+                });
+                match *child {
+                    Child::Node { location: child_location, .. } => {
+                        location = child_location.relative(location.depth);
+                        continue 'descend;
+                    }
+                    Child::Leaf(_) => {
+                        levels.reverse();
+                        return Some(Proof { levels: levels });
+                    }
+                }
+            }
+            return None;
+        }
+    }
+}
+
+impl<T, M> Collection<T, M>
+    where T: Val + Ord,
+          M: Meta<T> + SubMeta<Max<T>>
+{
+    /// Returns a proof that `t` is a member of the set, checked against
+    /// the digest `S` of the root, or `None` if it is not a member.
+    pub fn prove<S>(&self, t: &T) -> Option<Proof<S>>
+        where S: ProofDigest<T>,
+              M: SubMeta<S>
+    {
+        let search = Max::from_t(t);
+        self.prove_by(|child| match *child {
+            Child::Node { ref meta, .. } => {
+                let max: Cow<Max<T>> = meta.submeta();
+                if *max < search {
+                    Pick::Skip
+                } else {
+                    Pick::Take
+                }
+            }
+            Child::Leaf(ref leaf) => {
+                if leaf < t {
+                    Pick::Skip
+                } else if leaf == t {
+                    Pick::Take
+                } else {
+                    Pick::Stop
+                }
+            }
+        })
+    }
+}
+
+impl<T, M> Collection<T, M>
+    where T: Val,
+          M: Meta<T> + SubMeta<Cardinality<usize>>
+{
+    /// Returns a proof that the element at index `i` is part of the
+    /// vector, checked against the digest `S` of the root, or `None` if
+    /// the index is out of bounds.
+    ///
+    /// With `S` a `Digest`, the proof also proves the index.
+    pub fn prove_index<S>(&self, i: usize) -> Option<Proof<S>>
+        where S: ProofDigest<T>,
+              M: SubMeta<S>
+    {
+        let mut skip = i;
+        self.prove_by(|child| {
+            let count = match *child {
+                Child::Node { ref meta, .. } => {
+                    let c: Cow<Cardinality<usize>> = meta.submeta();
+                    *c.inner()
+                }
+                Child::Leaf(_) => 1,
+            };
+            if skip < count {
+                Pick::Take
+            } else {
+                skip -= count;
+                Pick::Skip
+            }
+        })
+    }
+}
+
+impl<T, M> Collection<T, M>
+    where T: Val + Ord,
+          M: Meta<T> + SubMeta<Max<T>>
+{
+    /// Returns a proof that the elements `t` of the set where
+    /// `from <= t < to` are exactly the ones returned by verifying it,
+    /// checked against the digest `S` of the root. Returns `None` if the
+    /// set is empty.
+    pub fn prove_range<S>(&self,
+                          from: &T,
+                          to: &T)
+                          -> Option<RangeProof<T, S>>
+        where S: ProofDigest<T>,
+              M: SubMeta<S>
+    {
+        if self.stash.get(self.root).children.is_empty() {
+            return None;
+        }
+        let range = (Max::from_t(from), Max::from_t(to));
+        Some(RangeProof { parts: self.range_parts(self.root, Some(&range)) })
+    }
+
+    /// Returns a proof that `t` is not in the set, holding the elements
+    /// right before and after where it would be, checked against the
+    /// digest `S` of the root. Returns `None` if `t` is in the set, or if
+    /// the set is empty.
+    pub fn prove_absent<S>(&self, t: &T) -> Option<RangeProof<T, S>>
+        where S: ProofDigest<T>,
+              M: SubMeta<S>
+    {
+        if self.member(t) {
+            return None;
+        }
+        self.prove_range(t, t)
+    }
+
+    // The parts of the node at `location` for a proof of `range`, or if
+    // `range` is `None`, of the last element below it.
+    fn range_parts<S>(&self,
+                      location: Location<T, M>,
+                      range: Option<&(Max<T>, Max<T>)>)
+                      -> Vec<Part<T, S>>
+        where S: ProofDigest<T>,
+              M: SubMeta<S>
+    {
+        let node = self.stash.get(location);
+        let len = node.children.len();
+        let maxes: Vec<Max<T>> = node.children
+            .iter()
+            .map(|child| match *child {
+                Child::Leaf(ref t) => Max::from_t(t),
+                Child::Node { ref meta, .. } => {
+                    let max: Cow<Max<T>> = meta.submeta();
+                    max.into_owned()
+                }
+            })
+            .collect();
+
+        node.children
+            .iter()
+            .enumerate()
+            .map(|(i, child)| {
+                let expand = match range {
+                    // Only the last element
+                    None => {
+                        if i + 1 == len {
+                            Some(None)
+                        } else {
+                            None
+                        }
+                    }
+                    Some((from, to)) => {
+                        let first = maxes.iter()
+                            .position(|max| max >= from)
+                            .unwrap_or(len);
+                        if i + 1 == first {
+                            // Holds the element right before the range
+                            Some(None)
+                        } else if i >= first &&
+                                  (i == first || maxes[i - 1] < *to) {
+                            Some(range)
+                        } else {
+                            None
+                        }
+                    }
+                };
+                match (expand, child) {
+                    (None, _) => Part::Pruned(digest_of(child)),
+                    (Some(_), Child::Leaf(t)) => Part::Leaf(t.clone()),
+                    (Some(range),
+                     Child::Node { location: child_location, .. }) => {
+                        Part::Node(self.range_parts(
+                            child_location.relative(location.depth), range))
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+// The digest `S` of a child
+fn digest_of<T, M, S>(child: &Child<T, M>) -> S
+    where T: Val,
+          M: Meta<T> + SubMeta<S>,
+          S: Meta<T>
+{
+    match *child {
+        Child::Leaf(ref t) => S::from_t(t),
+        Child::Node { ref meta, .. } => {
+            let s: Cow<S> = meta.submeta();
+            s.into_owned()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    const LOTS: usize = 100_000;
+
+    use std::hash::{Hash, Hasher};
+
+    use seahash::SeaHasher;
+
+    use meta::Meta;
+    use meta::max::Max;
+    use meta::digest::Digest;
+    use meta::checksum;
+
+    use collection::Collection;
+
+    use ops::set::SetOps;
+
+    use super::Proof;
+
+    #[test]
+    fn prove_range() {
+        let mut set = Set::new();
+
+        assert!(set.prove_range::<Digest<SeaHasher>>(&0, &10).is_none());
+
+        for i in 0..LOTS {
+            set.insert(i * 2);
+        }
+
+        let digest = set.digest::<Digest<SeaHasher>>().unwrap();
+
+        let proof = set.prove_range::<Digest<SeaHasher>>(&1000, &2000).unwrap();
+        let found = proof.verify(&digest, &1000, &2000).unwrap();
+        let expected: Vec<_> = (500..1000).map(|i| i * 2).collect();
+        assert!(found.into_iter().cloned().eq(expected.into_iter()));
+
+        // Sub-ranges are covered as well
+        let found = proof.verify(&digest, &1500, &1600).unwrap();
+        assert_eq!(found.len(), 50);
+
+        // But not the ranges with elements outside of the proof
+        assert!(proof.verify(&digest, &900, &2000).is_none());
+        assert!(proof.verify(&digest, &1000, &2100).is_none());
+
+        // Nor other sets
+        let mut other = Set::new();
+        other.insert(0);
+        let other_digest = other.digest::<Digest<SeaHasher>>().unwrap();
+        assert!(proof.verify(&other_digest, &1000, &2000).is_none());
+
+        // Ranges at the ends, and outside of the set
+        let proof = set.prove_range::<Digest<SeaHasher>>(&0, &10).unwrap();
+        assert_eq!(proof.verify(&digest, &0, &10).unwrap().len(), 5);
+        let end = LOTS * 2;
+        let proof = set.prove_range::<Digest<SeaHasher>>(&(end - 10), &end)
+            .unwrap();
+        assert_eq!(proof.verify(&digest, &(end - 10), &end).unwrap().len(),
+                   5);
+        let proof = set.prove_range::<Digest<SeaHasher>>(&end, &(end * 2))
+            .unwrap();
+        assert_eq!(proof.verify(&digest, &end, &(end * 2)), Some(vec![]));
+        let proof = set.prove_range::<Digest<SeaHasher>>(&1001, &1002).unwrap();
+        assert_eq!(proof.verify(&digest, &1001, &1002), Some(vec![]));
+    }
+
+    #[test]
+    fn prove_absent() {
+        let mut set = Set::new();
+
+        assert!(set.prove_absent::<Digest<SeaHasher>>(&0).is_none());
+
+        for i in 0..LOTS {
+            set.insert(i * 2 + 1);
+        }
+
+        let digest = set.digest::<Digest<SeaHasher>>().unwrap();
+
+        for i in 0..LOTS / 100 {
+            let t = i * 200;
+            let proof = set.prove_absent::<Digest<SeaHasher>>(&t).unwrap();
+            assert!(proof.verify_absent(&digest, &t));
+            // Does not prove the absence of anything else
+            assert!(!proof.verify_absent(&digest, &(t + 1)));
+            assert!(!proof.verify_absent(&digest, &(t + 400)));
+        }
+
+        let end = LOTS * 2;
+        let proof = set.prove_absent::<Digest<SeaHasher>>(&end).unwrap();
+        assert!(proof.verify_absent(&digest, &end));
+        assert!(proof.verify_absent(&digest, &(end * 2)));
+
+        assert!(set.prove_absent::<Digest<SeaHasher>>(&1).is_none());
+
+        // Once the element is there, the proof no longer holds
+        let proof = set.prove_absent::<Digest<SeaHasher>>(&1000).unwrap();
+        set.insert(1000);
+        let digest = set.digest::<Digest<SeaHasher>>().unwrap();
+        assert!(!proof.verify_absent(&digest, &1000));
+    }
+
+    collection!(Set<T> {
+        max: Max<T>,
+        digest: Digest<SeaHasher>,
+    } where T: Ord + Hash);
+
+    #[test]
+    fn prove() {
+        let mut set = Set::new();
+
+        assert!(set.digest::<Digest<SeaHasher>>().is_none());
+        assert!(set.prove::<Digest<SeaHasher>>(&0).is_none());
+
+        for i in 0..LOTS {
+            set.insert(i * 2);
+        }
+
+        let digest = set.digest::<Digest<SeaHasher>>().unwrap();
+
+        for i in 0..LOTS / 100 {
+            let t = i * 200;
+            let proof = set.prove::<Digest<SeaHasher>>(&t).unwrap();
+            assert!(proof.root(&t) == digest);
+            // The proof does not hold for any other element
+            assert!(proof.root(&(t + 2)) != digest);
+        }
+
+        assert!(set.prove::<Digest<SeaHasher>>(&1).is_none());
+        assert!(set.prove::<Digest<SeaHasher>>(&(LOTS * 2)).is_none());
+
+        // A client only needs the digest and the transferred proof
+        let t = 400;
+        let levels = set.prove::<Digest<SeaHasher>>(&t).unwrap().into_levels();
+        let proof = Proof::from_levels(levels);
+        assert!(proof.verify(&digest, &t));
+        assert!(!proof.verify(&digest, &(t + 2)));
+
+        // Proofs are only valid for the collection they were made from
+        let proof = set.prove::<Digest<SeaHasher>>(&0).unwrap();
+        set.insert(1);
+        assert!(proof.root(&0) != set.digest::<Digest<SeaHasher>>().unwrap());
+    }
+
+    #[test]
+    fn forged_leaf() {
+        let mut set = Set::new();
+        for i in 0..LOTS as u64 {
+            set.insert((i, i));
+        }
+        let digest = set.digest::<Digest<SeaHasher>>().unwrap();
+
+        let hash = |t: &(u64, u64)| {
+            let mut hasher = SeaHasher::default();
+            t.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        // An element with at least two siblings before it in its node
+        let (t, mut levels) = (0..LOTS as u64)
+            .map(|i| (i, i))
+            .map(|t| (t, set.prove::<Digest<SeaHasher>>(&t).unwrap()))
+            .map(|(t, proof)| (t, proof.into_levels()))
+            .find(|found| found.1[0].0.len() >= 2)
+            .unwrap();
+
+        // Pass the first two siblings off as a single element, that hashes
+        // the same as the node holding just the two of them would
+        let k = levels[0].0.len() as u64;
+        let (a, b) = ((t.0 - k, t.1 - k), (t.0 - k + 1, t.1 - k + 1));
+        let forged = (checksum::combine(hash(&a), 1),
+                      checksum::combine(hash(&b), 1));
+        assert!(!set.member(&forged));
+
+        let mut after = levels[0].0.split_off(2);
+        after.push(Digest::from_t(&t));
+        after.append(&mut levels[0].1);
+        levels[0] = (vec![], after);
+        let proof = Proof::from_levels(levels);
+
+        assert!(!proof.verify(&digest, &forged));
+        assert_eq!(proof.root(&forged).count(), LOTS - 1);
+    }
+
+    mod indexed {
+        use std::hash::Hash;
+
+        use seahash::SeaHasher;
+
+        use meta::cardinality::Cardinality;
+        use meta::digest::Digest;
+
+        use collection::Collection;
+
+        use ops::vector::VectorOps;
+
+        use super::LOTS;
+
+        collection!(Vector<T> {
+            cardinality: Cardinality<usize>,
+            digest: Digest<SeaHasher>,
+        } where T: Hash);
+
+        #[test]
+        fn prove_index() {
+            let mut vec = Vector::new();
+
+            assert!(vec.prove_index::<Digest<SeaHasher>>(0).is_none());
+
+            for i in 0..LOTS {
+                vec.push(i % 100);
+            }
+
+            let digest = vec.digest::<Digest<SeaHasher>>().unwrap();
+            assert_eq!(digest.count(), LOTS);
+
+            for i in 0..LOTS / 100 {
+                let i = i * 97;
+                let proof = vec.prove_index::<Digest<SeaHasher>>(i).unwrap();
+                assert!(proof.root(&(i % 100)) == digest);
+                assert!(proof.root(&(i % 100 + 1)) != digest);
+                assert_eq!(proof.index(), i);
+                assert!(proof.verify_at(&digest, i, &(i % 100)));
+                assert!(!proof.verify_at(&digest, i + 1, &(i % 100)));
+            }
+
+            assert!(vec.prove_index::<Digest<SeaHasher>>(LOTS).is_none());
+        }
+    }
+}
@@ -6,6 +6,7 @@ use std::mem;
 use Val;
 use tree::node::{Node, Child};
 use meta::Meta;
+#[cfg(feature = "visualize")]
 use html::Html;
 
 pub struct Stash<T, M>
@@ -71,6 +72,7 @@ impl<'a, T, M> RelStash<'a, T, M>
     }
 }
 
+#[cfg(feature = "visualize")]
 impl<'a, T, M> RelStash<'a, T, M>
     where T: 'a + Val + fmt::Debug,
           M: 'a + Meta<T>
@@ -198,6 +200,29 @@ impl<T, M> Stash<T, M>
         }
     }
 
+    // The number of nodes in this stash, reachable or not
+    pub fn len(&self) -> usize {
+        self.uniq.len() +
+        self.shared.iter().map(|nodes| nodes.len()).sum::<usize>()
+    }
+
+    // The bytes allocated for the nodes owned by this stash only, and for
+    // the ones in layers shared with its clones
+    pub fn bytes(&self) -> (usize, usize) {
+        (layer_bytes(&self.uniq),
+         self.shared.iter().map(|nodes| layer_bytes(nodes)).sum())
+    }
+
+    // Does `loc` resolve to a node in this stash?
+    pub fn contains(&self, loc: Location<T, M>) -> bool {
+        let Location { ofs, depth, .. } = loc;
+        if depth == 0 {
+            ofs < self.uniq.len()
+        } else {
+            depth <= self.shared.len() && ofs < self.shared[depth - 1].len()
+        }
+    }
+
     pub fn get_clone(&self, loc: Location<T, M>) -> Node<T, M> {
         let Location { ofs, depth, .. } = loc;
         if depth == 0 {
@@ -236,7 +261,7 @@ impl<T, M> Stash<T, M>
         if depth == 0 {
             mem::replace(&mut self.uniq[ofs], Node::new())
         } else {
-            self.shared[depth - 1][ofs].clone()
+            self.get_clone(loc)
         }
     }
 
@@ -277,6 +302,15 @@ impl<T, M> Stash<T, M>
         }
     }
 
+    // A copy of the stash, sharing the layers it shares with its clones,
+    // and copying the nodes it owns
+    pub fn clone_ref(&self) -> Self {
+        Stash {
+            uniq: self.uniq.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+
     pub fn clone_mut(&mut self, root: &mut Location<T, M>) -> Self {
         let Location { ref mut depth, .. } = *root;
         if *depth == 0 {
@@ -291,6 +325,7 @@ impl<T, M> Stash<T, M>
     }
 }
 
+#[cfg(feature = "visualize")]
 impl<T, M> Stash<T, M>
     where T: Val + fmt::Debug,
           M: Meta<T>
@@ -345,6 +380,18 @@ impl<T, M> Stash<T, M>
     }
 }
 
+// The bytes allocated for `nodes` and their children, not counting
+// what the elements themselves point to
+fn layer_bytes<T, M>(nodes: &Vec<Node<T, M>>) -> usize
+    where T: Val,
+          M: Meta<T>
+{
+    nodes.capacity() * mem::size_of::<Node<T, M>>() +
+    nodes.iter()
+        .map(|node| node.children.capacity() * mem::size_of::<Child<T, M>>())
+        .sum::<usize>()
+}
+
 impl<T, M> fmt::Debug for Location<T, M>
     where T: Val + fmt::Debug,
           M: Meta<T>
@@ -354,6 +401,7 @@ impl<T, M> fmt::Debug for Location<T, M>
     }
 }
 
+#[cfg(feature = "visualize")]
 impl<T, M> Html<T, M> for Location<T, M>
     where T: Val + fmt::Debug,
           M: Meta<T>
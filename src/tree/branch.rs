@@ -1,4 +1,3 @@
-use std::fmt;
 use std::cmp;
 
 use std::borrow::Cow;
@@ -6,10 +5,15 @@ use std::borrow::Cow;
 use Val;
 
 use tree::node::{Node, Child, RemoveResult, InsertResult};
-use stash::{Stash, RelStash, Location};
+use stash::{Stash, Location};
 use tree::level::{Level, Relative, Opposite, Beginning, End};
 use meta::{Meta, Select, Selection, Found, SubMeta};
 
+#[cfg(feature = "visualize")]
+use std::fmt;
+#[cfg(feature = "visualize")]
+use stash::RelStash;
+#[cfg(feature = "visualize")]
 use html::Html;
 
 pub struct Branch<T, M, R>
@@ -241,6 +245,7 @@ impl<T, M, R> Branch<T, M, R>
     // used for maps, which are only balanced on key.
     pub fn update(&mut self, t: T, stash: &mut Stash<T, M>) {
         self.leaf_mut(stash).map(|l| *l = t);
+        self.propagate(stash);
     }
 
     pub fn rebalance(&mut self,
@@ -254,7 +259,8 @@ impl<T, M, R> Branch<T, M, R>
                 self.propagate(stash);
             }
 
-            if new_weight > 0 {
+            // The last element of the collection never splits
+            if new_weight > 0 && !self.bottom().last(stash) {
                 self.ensure_depth(new_weight + 1, stash);
                 self.split(new_weight, stash);
                 self.propagate(stash);
@@ -279,7 +285,10 @@ impl<T, M, R> Branch<T, M, R>
                 Some(t)
             }
             RemoveResult::Merge { t, depth } => {
-                self.merge(depth, stash);
+                // Removing from the end, there is nothing to merge with
+                if !R::from_end() {
+                    self.merge(depth, stash);
+                }
                 self.propagate(stash);
                 self.find_first_root(stash);
                 Some(t)
@@ -416,8 +425,10 @@ impl<'a, T, M, R> Branch<T, M, R>
               E: Meta<T> + PartialEq
     {
         let mut depth = 0;
+        let max_depth = cmp::min(self.depth(), other.depth());
 
-        while self.level_meta(depth, stash).map(|m| {
+        while depth < max_depth &&
+              self.level_meta(depth, stash).map(|m| {
                                                     (*(*m).submeta()).clone() as
                                                     E
                                                 }) ==
@@ -532,6 +543,7 @@ impl<T, M, R> Branch<T, M, R>
 //     }
 // }
 
+#[cfg(feature = "visualize")]
 impl<T, M, R> Html<T, M> for Branch<T, M, R>
     where T: Val + fmt::Debug,
           M: Meta<T>,
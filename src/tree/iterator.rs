@@ -1,10 +1,15 @@
-use std::fmt;
+use std::mem;
+use std::collections::VecDeque;
 
 use Val;
-use tree::branch::Branch;
+use tree::branch::{Branch, BranchResult};
+use tree::node::Child;
 use tree::level::{Relative, Beginning, End};
 use stash::Stash;
-use meta::Meta;
+use meta::{Meta, SubMeta, Select};
+#[cfg(feature = "visualize")]
+use std::fmt;
+#[cfg(feature = "visualize")]
 use html::Html;
 use collection::Collection;
 
@@ -15,7 +20,7 @@ pub struct Iter<'a, T, M, R>
           R: Relative
 {
     stash: &'a Stash<T, M>,
-    branch: Branch<T, M, R>,
+    branch: Option<Branch<T, M, R>>,
     first: bool,
 }
 
@@ -28,7 +33,16 @@ impl<'a, T, M, R> Iter<'a, T, M, R>
     pub fn new(branch: Branch<T, M, R>, stash: &'a Stash<T, M>) -> Self {
         Iter {
             stash: stash,
-            branch: branch,
+            branch: Some(branch),
+            first: true,
+        }
+    }
+
+    /// Constructs an iterator that yields no elements.
+    pub fn empty(stash: &'a Stash<T, M>) -> Self {
+        Iter {
+            stash: stash,
+            branch: None,
             first: true,
         }
     }
@@ -42,22 +56,131 @@ impl<'a, T, M, R> Iterator for Iter<'a, T, M, R>
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
+        let branch = match self.branch {
+            Some(ref mut branch) => branch,
+            None => return None,
+        };
         if self.first == true {
             self.first = false;
-            self.branch.leaf(&self.stash)
+            branch.leaf(self.stash)
         } else {
-            match self.branch.step(&self.stash) {
-                Some(_) => self.branch.leaf(&self.stash),
+            match branch.step(self.stash) {
+                Some(_) => branch.leaf(self.stash),
                 None => None,
             }
         }
     }
 }
 
+/// A draining iterator, yielding owned elements from the front of a
+/// Collection.
+pub struct Drain<T, M>
+    where T: Val,
+          M: Meta<T>
+{
+    collection: Collection<T, M>,
+}
+
+impl<T, M> Drain<T, M>
+    where T: Val,
+          M: Meta<T>
+{
+    /// Constructs a new draining iterator, taking ownership of `collection`
+    pub fn new(collection: Collection<T, M>) -> Self {
+        Drain { collection: collection }
+    }
+}
+
+impl<T, M> Iterator for Drain<T, M>
+    where T: Val,
+          M: Meta<T>
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let collection = &mut self.collection;
+        let mut branch: Branch<_, _, Beginning> =
+            Branch::first(collection.root, &collection.stash);
+        let ret = branch.remove(collection.divisor, &mut collection.stash);
+        collection.root = branch.root();
+        ret
+    }
+}
+
+/// An owning iterator over a Collection, yielding its elements in order.
+///
+/// Nodes shared with other collections are cloned once each, nodes owned
+/// only by this one are taken apart without cloning.
+pub struct IntoIter<T, M>
+    where T: Val,
+          M: Meta<T>
+{
+    stash: Stash<T, M>,
+    // The remaining children of each node on the way down to the
+    // current leaf
+    stack: Vec<VecDeque<Child<T, M>>>,
+}
+
+impl<T, M> Iterator for IntoIter<T, M>
+    where T: Val,
+          M: Meta<T>
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let child = match self.stack.last_mut() {
+                Some(children) => children.pop_front(),
+                None => return None,
+            };
+            match child {
+                Some(Child::Leaf(t)) => return Some(t),
+                Some(Child::Node { location, .. }) => {
+                    // Removed nodes have their locations made absolute
+                    let node = self.stash.remove(location);
+                    self.stack.push(node.children);
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl<T, M> IntoIterator for Collection<T, M>
+    where T: Val,
+          M: Meta<T>
+{
+    type Item = T;
+    type IntoIter = IntoIter<T, M>;
+
+    fn into_iter(self) -> IntoIter<T, M> {
+        let mut stash = self.stash;
+        let root = stash.remove(self.root);
+        IntoIter {
+            stash: stash,
+            stack: vec![root.children],
+        }
+    }
+}
+
 impl<T, M> Collection<T, M>
     where T: Val,
           M: Meta<T>
 {
+    /// Removes all elements from the Collection, returning them in order
+    /// as an iterator of owned values.
+    ///
+    /// Snapshots are kept.
+    pub fn drain(&mut self) -> Drain<T, M> {
+        // Only the tree is taken, the snapshots stay
+        let mut drained = Collection::new();
+        mem::swap(&mut drained.root, &mut self.root);
+        mem::swap(&mut drained.stash, &mut self.stash);
+        Drain::new(drained)
+    }
+
     /// Returns an iterator over Collection
     pub fn iter<'a>(&'a self) -> Iter<'a, T, M, Beginning> {
         let branch: Branch<_, _, Beginning> = Branch::first(self.root,
@@ -65,13 +188,44 @@ impl<T, M> Collection<T, M>
         Iter::new(branch, &self.stash)
     }
 
+    /// Returns an iterator over Collection, starting at the position
+    /// selected by `search`
+    pub fn iter_from_using<'a, S>(&'a self,
+                                  mut search: S)
+                                  -> Iter<'a, T, M, Beginning>
+        where S: Meta<T> + Select<T>,
+              M: SubMeta<S>
+    {
+        match Branch::new_full(self.root, &mut search, &self.stash) {
+            BranchResult::Hit(branch) |
+            BranchResult::Between(branch) => Iter::new(branch, &self.stash),
+            BranchResult::Miss => Iter::empty(&self.stash),
+        }
+    }
+
     /// Returns a reverse iterator over Collection
     pub fn iter_rev<'a>(&'a self) -> Iter<'a, T, M, End> {
         let branch: Branch<_, _, End> = Branch::first(self.root, &self.stash);
         Iter::new(branch, &self.stash)
     }
+
+    /// Returns a reverse iterator over Collection, starting at the
+    /// position selected by `search`, searching from the end
+    pub fn iter_rev_from_using<'a, S>(&'a self,
+                                      mut search: S)
+                                      -> Iter<'a, T, M, End>
+        where S: Meta<T> + Select<T>,
+              M: SubMeta<S>
+    {
+        match Branch::new_full(self.root, &mut search, &self.stash) {
+            BranchResult::Hit(branch) |
+            BranchResult::Between(branch) => Iter::new(branch, &self.stash),
+            BranchResult::Miss => Iter::empty(&self.stash),
+        }
+    }
 }
 
+#[cfg(feature = "visualize")]
 impl<'a, T, M, R> Iter<'a, T, M, R>
     where T: 'a + Val + fmt::Debug,
           M: 'a + Meta<T>,
@@ -79,6 +233,9 @@ impl<'a, T, M, R> Iter<'a, T, M, R>
 {
     /// Debug HTML output
     pub fn _html(&self) -> String {
-        self.branch._html(self.stash.top())
+        self.branch
+            .as_ref()
+            .map(|branch| branch._html(self.stash.top()))
+            .unwrap_or_default()
     }
 }
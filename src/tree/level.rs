@@ -1,12 +1,16 @@
-use std::fmt;
 use std::mem;
 use std::marker::PhantomData;
 use std::borrow::Cow;
 
 use Val;
-use stash::{Stash, RelStash, Location};
+use stash::{Stash, Location};
 use tree::node::{Node, Child, InsertResult, RemoveResult};
 use meta::{Meta, SubMeta, Select, Selection, Found};
+#[cfg(feature = "visualize")]
+use std::fmt;
+#[cfg(feature = "visualize")]
+use stash::RelStash;
+#[cfg(feature = "visualize")]
 use html::Html;
 
 pub trait Relative {
@@ -46,7 +50,12 @@ impl Relative for End {
         mem::swap(a, b)
     }
     fn at(i: usize, len: usize) -> usize {
-        (len - i).saturating_sub(1)
+        // Past the beginning, point outside the node like `Beginning` does
+        if i < len {
+            len - i - 1
+        } else {
+            len
+        }
     }
     fn insert(i: usize, len: usize) -> usize {
         len - i
@@ -195,6 +204,12 @@ impl<T, M, R> Level<T, M, R>
         self.ofs += 1;
     }
 
+    /// Is the level pointing at the last child of its node?
+    pub fn last(&self, stash: &Stash<T, M>) -> bool {
+        let len = stash.get(self.location).len();
+        R::at(self.ofs, len) + 1 >= len
+    }
+
     pub fn steppable(&mut self, stash: &Stash<T, M>) -> bool {
         match stash.get(self.location).child(self.ofs + 1) {
             Some(_) => true,
@@ -252,12 +267,15 @@ impl<T, M, R> Level<T, M, R>
         let mut child_node_loc = None;
         {
             let node = stash.get_mut(&mut self.location);
-            match node.remove(self.ofs) {
-                Some(Child::Node { location, .. }) => {
-                    child_node_loc = Some(location);
+            let len = node.len();
+            if let Some(Child::Node { location, .. }) =
+                node.remove(R::at(self.ofs, len)) {
+                child_node_loc = Some(location);
+                // counting from the end, the offset already points
+                // at the preceding child
+                if !R::from_end() {
                     self.ofs = self.ofs.saturating_sub(1);
                 }
-                _ => (),
             }
         }
         child_node_loc.map(|loc| stash.remove(loc))
@@ -281,7 +299,9 @@ impl<T, M, R> Level<T, M, R>
                     divisor: usize,
                     stash: &mut Stash<T, M>)
                     -> RemoveResult<T> {
-        stash.get_mut(&mut self.location).remove_t(self.ofs, divisor)
+        let node = stash.get_mut(&mut self.location);
+        let len = node.len();
+        node.remove_t(R::at(self.ofs, len), divisor)
     }
 
     pub fn split(&mut self, stash: &mut Stash<T, M>) -> Child<T, M> {
@@ -407,6 +427,7 @@ impl<T, M, R> Level<T, M, R>
     }
 }
 
+#[cfg(feature = "visualize")]
 impl<T, M, R> Html<T, M> for Level<T, M, R>
     where T: Val + fmt::Debug,
           M: Meta<T>,
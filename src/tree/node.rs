@@ -1,12 +1,16 @@
 use std::collections::VecDeque;
-use std::fmt;
 use std::marker::PhantomData;
 
 use std::borrow::Cow;
 
 use Val;
-use stash::{RelStash, Location};
+use stash::Location;
 use meta::{Meta, SubMeta};
+#[cfg(feature = "visualize")]
+use std::fmt;
+#[cfg(feature = "visualize")]
+use stash::RelStash;
+#[cfg(feature = "visualize")]
 use html::Html;
 
 use meta::checksum::CheckSum;
@@ -273,19 +277,23 @@ impl<T, M> Node<T, M>
     }
 }
 
-impl<'a, T, M> PartialEq for Node<T, M>
+impl<T, M> Node<T, M>
     where T: Val,
-          M: Meta<T> + SubMeta<CheckSum<u64>>
+          M: Meta<T>
 {
-    fn eq(&self, other: &Self) -> bool {
+    // Compares the checksums `E` of the children of two nodes
+    pub fn eq_using<E>(&self, other: &Self) -> bool
+        where E: Clone + PartialEq,
+              M: SubMeta<E>
+    {
         if self.len() != other.len() {
             return false;
         }
         for i in 0..self.len() {
             let ma = self.children[i].meta();
             let mb = other.children[i].meta();
-            let a: Cow<CheckSum<u64>> = (*ma).submeta();
-            let b: Cow<CheckSum<u64>> = (*mb).submeta();
+            let a: Cow<E> = (*ma).submeta();
+            let b: Cow<E> = (*mb).submeta();
             if a != b {
                 return false;
             }
@@ -294,6 +302,16 @@ impl<'a, T, M> PartialEq for Node<T, M>
     }
 }
 
+impl<T, M> PartialEq for Node<T, M>
+    where T: Val,
+          M: Meta<T> + SubMeta<CheckSum<u64>>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_using::<CheckSum<u64>>(other)
+    }
+}
+
+#[cfg(feature = "visualize")]
 impl<T, M> Html<T, M> for Node<T, M>
     where T: Val + fmt::Debug,
           M: Meta<T>
@@ -310,6 +328,7 @@ impl<T, M> Html<T, M> for Node<T, M>
     }
 }
 
+#[cfg(feature = "visualize")]
 impl<T, M> Html<T, M> for Child<T, M>
     where T: Val + fmt::Debug,
           M: Meta<T>
@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use Val;
+use collection::Collection;
+use meta::Meta;
+use stash::Location;
+use tree::node::{Node, Child};
+
+impl<T, M> Collection<T, M>
+    where T: Val + fmt::Debug,
+          M: Meta<T>
+{
+    /// Produces a DOT graph of the nodes of this Collection, to render
+    /// with Graphviz. For debug use only.
+    pub fn to_dot(&self) -> String {
+        Self::to_dot_many(&[self])
+    }
+
+    /// Produces a DOT graph of the nodes of several Collections, where
+    /// nodes shared between them are drawn once. For debug use only.
+    pub fn to_dot_many(collections: &[&Self]) -> String {
+        let mut s = String::from("digraph collection {\n");
+        let mut seen = HashSet::new();
+        for (i, collection) in collections.iter().enumerate() {
+            let root = collection.stash.get(collection.root);
+            s += &format!("  c{} [label=\"{}\", shape=plaintext];\n", i, i);
+            s += &format!("  c{} -> {};\n", i, id(root));
+            collection.dot_node(collection.root, &mut seen, &mut s);
+        }
+        s += "}\n";
+        s
+    }
+
+    // Writes the node at `location` and everything below it to `s`,
+    // unless it was already written.
+    fn dot_node(&self,
+                location: Location<T, M>,
+                seen: &mut HashSet<usize>,
+                s: &mut String) {
+        let node = self.stash.get(location);
+        if !seen.insert(node as *const _ as usize) {
+            return;
+        }
+        let node_id = id(node);
+        *s += &format!("  {} [label=\"{:?}\"];\n", node_id, location);
+        for (i, child) in node.children.iter().enumerate() {
+            match *child {
+                Child::Node { location: child_location, .. } => {
+                    let child_location =
+                        child_location.relative(location.depth);
+                    let child_id = id(self.stash.get(child_location));
+                    *s += &format!("  {} -> {};\n", node_id, child_id);
+                    self.dot_node(child_location, seen, s);
+                }
+                Child::Leaf(ref t) => {
+                    let label = format!("{:?}", t)
+                        .replace('\\', "\\\\")
+                        .replace('"', "\\\"");
+                    *s += &format!("  {}_{} [label=\"{}\", shape=box];\n",
+                                   node_id,
+                                   i,
+                                   label);
+                    *s += &format!("  {} -> {}_{};\n", node_id, node_id, i);
+                }
+            }
+        }
+    }
+}
+
+// Nodes are identified by their address, which is the same in all
+// collections sharing them
+fn id<T, M>(node: &Node<T, M>) -> String
+    where T: Val,
+          M: Meta<T>
+{
+    format!("n{:x}", node as *const _ as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::Hash;
+
+    use meta::cardinality::Cardinality;
+    use collection::Collection;
+    use ops::vector::VectorOps;
+
+    collection!(Vector<T> {
+        cardinality: Cardinality<usize>,
+    } where T: Hash);
+
+    fn nodes(dot: &str) -> usize {
+        dot.lines().filter(|line| line.contains("[label=\"loc")).count()
+    }
+
+    #[test]
+    fn to_dot() {
+        let mut a = Vector::new();
+
+        for i in 0..1000 {
+            a.push(i);
+        }
+
+        let dot = a.to_dot();
+        assert!(dot.starts_with("digraph collection {\n"));
+        assert!(dot.ends_with("}\n"));
+        for i in 0..1000 {
+            assert!(dot.contains(&format!("[label=\"{}\", shape=box]", i)));
+        }
+
+        let mut quoted = Vector::new();
+        quoted.push("say \"hi\"");
+        assert!(quoted.to_dot().contains("[label=\"\\\"say \\\\\\\"hi"));
+
+        let mut b = a.clone_mut();
+        b.push(1000);
+
+        let one = nodes(&a.to_dot());
+        let both = nodes(&Vector::to_dot_many(&[&a, &b]));
+        assert!(both > one);
+        assert!(both < one * 2);
+    }
+
+    #[test]
+    #[cfg(feature = "visualize")]
+    fn to_html() {
+        let mut vec = Vector::new();
+
+        for i in 0..100 {
+            vec.push(i);
+        }
+
+        let html = vec.to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.ends_with("</html>\n"));
+        assert!(html.contains("<style>"));
+        for i in 0..100 {
+            assert!(html.contains(&format!(">{}</div>", i)));
+        }
+    }
+}